@@ -0,0 +1,51 @@
+use soroban_sdk::{panic_with_error, unwrap::UnwrapOptimized, Env};
+
+use crate::{data_keys::DataKey, errors::instr_not_allowed, proposal::Proposal, threshold::FunKey};
+
+// when enabled, proposals can only call functions that are in the allowlist
+pub fn set_allow_mode(env: &Env, enabled: bool) {
+    env.storage().set(&DataKey::AllowMode, &enabled)
+}
+
+pub fn allow_mode(env: &Env) -> bool {
+    env.storage()
+        .get(&DataKey::AllowMode)
+        .unwrap_or(Ok(false))
+        .unwrap_optimized()
+}
+
+pub fn allow_fn(env: &Env, key: FunKey) {
+    env.storage().set(&DataKey::Allowed(key), &true)
+}
+
+pub fn disallow_fn(env: &Env, key: FunKey) {
+    env.storage().remove(&DataKey::Allowed(key))
+}
+
+pub fn is_allowed(env: &Env, key: FunKey) -> bool {
+    env.storage().has(&DataKey::Allowed(key))
+}
+
+// panics with the index of the first instruction that isn't allowed.
+// Instructions for the dao itself are always allowed, so that the allowlist can't lock
+// governance out of its own settings.
+pub fn check_allowed(env: &Env, proposal: &Proposal) {
+    if !allow_mode(env) {
+        return;
+    }
+
+    for (index, instr) in proposal.instr.iter().enumerate() {
+        let instr = instr.unwrap_optimized();
+        if instr.c_id == env.current_contract_id() {
+            continue;
+        }
+
+        let key = FunKey {
+            c_id: instr.c_id,
+            fun_name: instr.fun_name,
+        };
+        if !is_allowed(env, key) {
+            panic_with_error!(env, instr_not_allowed(index as u32))
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use soroban_sdk::{contracttype, unwrap::UnwrapOptimized, Address, Env};
+
+use crate::{
+    data_keys::DataKey,
+    proposal::{remove_votes, ProposalVoted},
+    token::get_dao_token_client,
+};
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VoteSide {
+    For,
+    Against,
+    Abstain,
+}
+
+// how an address voted on a proposal, and with how much power
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ballot {
+    pub side: VoteSide,
+    // goes down when a delegate below this voter votes itself
+    pub weight: i128,
+}
+
+// votes for and abstains cast with power from live delegators that didn't vote yet.
+// They can still vote against instead, which takes the power back.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Movable {
+    pub v_for: i128,
+    pub v_abstain: i128,
+}
+
+pub fn get_movable(env: &Env, prop_id: u32) -> Movable {
+    env.storage()
+        .get(&DataKey::Movable(prop_id))
+        .unwrap_or(Ok(Movable::default()))
+        .unwrap_optimized()
+}
+
+fn add_movable(env: &Env, prop_id: u32, side: VoteSide, amount: i128) {
+    let mut movable = get_movable(env, prop_id);
+    match side {
+        VoteSide::For => movable.v_for += amount,
+        VoteSide::Abstain => movable.v_abstain += amount,
+        // against votes that are taken back can only help the proposal
+        VoteSide::Against => return,
+    }
+    env.storage().set(&DataKey::Movable(prop_id), &movable)
+}
+
+pub fn get_ballot(env: &Env, prop_id: u32, voter: Address) -> Option<Ballot> {
+    env.storage()
+        .get(&DataKey::Ballot(ProposalVoted { voter, prop_id }))
+        .map(|ballot| ballot.unwrap_optimized())
+}
+
+fn set_ballot(env: &Env, prop_id: u32, voter: Address, ballot: &Ballot) {
+    env.storage()
+        .set(&DataKey::Ballot(ProposalVoted { voter, prop_id }), ballot)
+}
+
+// power below `of` in the delegation chain that was already voted with
+fn get_claimed(env: &Env, prop_id: u32, of: Address) -> i128 {
+    env.storage()
+        .get(&DataKey::Claimed(ProposalVoted { voter: of, prop_id }))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+fn add_claimed(env: &Env, prop_id: u32, of: Address, amount: i128) {
+    let claimed = get_claimed(env, prop_id, of.clone());
+    env.storage().set(
+        &DataKey::Claimed(ProposalVoted { voter: of, prop_id }),
+        &(claimed + amount),
+    )
+}
+
+// the power of `voter` at the start of the proposal includes its whole delegation chain,
+// without the delegates below it that already voted themselves
+pub fn vote_weight(env: &Env, prop_id: u32, voter: Address, power: i128) -> i128 {
+    power - get_claimed(env, prop_id, voter)
+}
+
+// store the ballot and take the part of its weight that flowed through live delegations
+// from the closest delegate above `voter` that voted.
+// The delegates in between remember it, so that it isn't counted again when they vote.
+pub fn cast_ballot(env: &Env, prop_id: u32, voter: Address, start_ledger: u32, ballot: Ballot) {
+    set_ballot(env, prop_id, voter.clone(), &ballot);
+
+    let client = get_dao_token_client(env);
+    // the delegators below that voted already took their part
+    let movable = client.live_at(&voter, &start_ledger) - get_claimed(env, prop_id, voter.clone());
+    if movable > 0 {
+        add_movable(env, prop_id, ballot.side, movable);
+    }
+
+    // power delegated with an expiry only counts for the address it was delegated to
+    let delegated = ballot.weight - client.timed_at(&voter, &start_ledger);
+    let mut of = voter;
+    loop {
+        let up = client.dlg_at(&of, &start_ledger);
+        if up == of {
+            break;
+        }
+        if let Some(mut upstream) = get_ballot(env, prop_id, up.clone()) {
+            upstream.weight -= delegated;
+            set_ballot(env, prop_id, up, &upstream);
+            remove_votes(env, prop_id, upstream.side, delegated);
+            add_movable(env, prop_id, upstream.side, -delegated);
+            break;
+        }
+        add_claimed(env, prop_id, up.clone(), delegated);
+        of = up;
+    }
+}
+
+// returns whether there was anything to remove
+pub fn remove_ballot(env: &Env, prop_id: u32, voter: Address) -> bool {
+    let ballot = DataKey::Ballot(ProposalVoted {
+        voter: voter.clone(),
+        prop_id,
+    });
+    let claimed = DataKey::Claimed(ProposalVoted { voter, prop_id });
+    let found = env.storage().has(&ballot) || env.storage().has(&claimed);
+
+    env.storage().remove(&ballot);
+    env.storage().remove(&claimed);
+    found
+}
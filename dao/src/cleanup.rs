@@ -0,0 +1,170 @@
+use soroban_sdk::{
+    contracttype, panic_with_error, serde::Serialize, unwrap::UnwrapOptimized, Address, BytesN,
+    Env, Vec,
+};
+
+use crate::{
+    ballot::remove_ballot,
+    committee::{find_committee, get_prop_committee},
+    data_keys::DataKey,
+    errors::ContractError,
+    proposal::{
+        executed, get_expiry, get_prop_supply, get_proposal, remove_active, remove_votes_counts,
+        votes_counts, ProposalVoted, VotesCount,
+    },
+    settings::now,
+    threshold::{passes, proposal_threshold},
+    token::get_dao_token_client,
+};
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PropOutcome {
+    Executed,
+    // passed, but not executed before the grace period ended
+    Expired,
+    // didn't get the votes to be executed
+    Defeated,
+}
+
+// what is kept of a proposal after its storage was cleaned up
+#[contracttype]
+#[derive(Clone)]
+pub struct PropSummary {
+    pub outcome: PropOutcome,
+    pub votes: VotesCount,
+    // sha256 of the serialized instructions
+    pub instr_hash: BytesN<32>,
+}
+
+// paid from the treasury to whoever cleans up a proposal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Bounty {
+    // paid for every voter whose entries a cleanup removes
+    pub amount: i128,
+    // what is left of the dao tokens set aside for bounties
+    pub fund: i128,
+}
+
+pub fn set_bounty(env: &Env, bounty: Bounty) {
+    if bounty.amount < 0 || bounty.fund < 0 {
+        panic_with_error!(env, ContractError::InvalidBounty)
+    }
+    env.storage().set(&DataKey::Bounty, &bounty)
+}
+
+pub fn get_bounty(env: &Env) -> Bounty {
+    env.storage()
+        .get(&DataKey::Bounty)
+        .unwrap_or(Ok(Bounty { amount: 0, fund: 0 }))
+        .unwrap_optimized()
+}
+
+pub fn get_summary(env: &Env, prop_id: u32) -> Option<PropSummary> {
+    env.storage()
+        .get(&DataKey::Summary(prop_id))
+        .map(|summary| summary.unwrap_optimized())
+}
+
+// whether the votes were enough to execute the proposal. Proposals of a revoked
+// committee can't be executed anymore, so they never pass.
+fn passed(env: &Env, prop_id: u32) -> bool {
+    let threshold = match get_prop_committee(env, prop_id) {
+        Some(comm_id) => match find_committee(env, comm_id) {
+            Some(committee) => committee.threshold,
+            None => return false,
+        },
+        None => proposal_threshold(env, &get_proposal(env, prop_id)),
+    };
+    passes(
+        &threshold,
+        &votes_counts(env, prop_id),
+        get_prop_supply(env, prop_id),
+    )
+}
+
+// outcome of a proposal that can't change anymore
+fn final_outcome(env: &Env, prop_id: u32) -> PropOutcome {
+    let end_time = get_proposal(env, prop_id).end_time;
+    // executed proposals can still be voted on until they end
+    if now(env) >= end_time && executed(env, prop_id) {
+        PropOutcome::Executed
+    } else if now(env) >= get_expiry(env, prop_id) && !executed(env, prop_id) {
+        if passed(env, prop_id) {
+            PropOutcome::Expired
+        } else {
+            PropOutcome::Defeated
+        }
+    } else {
+        panic_with_error!(env, ContractError::NotFinalized)
+    }
+}
+
+// replace the proposal with a summary
+fn summarize(env: &Env, prop_id: u32) {
+    let outcome = final_outcome(env, prop_id);
+    let instr = get_proposal(env, prop_id).instr;
+    let summary = PropSummary {
+        outcome,
+        votes: votes_counts(env, prop_id),
+        instr_hash: env.crypto().sha256(&instr.serialize(env)),
+    };
+    env.storage().set(&DataKey::Summary(prop_id), &summary);
+
+    env.storage().remove(&DataKey::Proposal(prop_id));
+    remove_votes_counts(env, prop_id);
+    env.storage().remove(&DataKey::Movable(prop_id));
+    env.storage().remove(&DataKey::PropSupply(prop_id));
+    env.storage().remove(&DataKey::PropStart(prop_id));
+    env.storage().remove(&DataKey::PropExpiry(prop_id));
+    env.storage().remove(&DataKey::PropComm(prop_id));
+    remove_active(env, prop_id);
+}
+
+// pay the bounty for `removed` voters to `to`, as far as the fund and the treasury allow
+fn pay_bounty(env: &Env, removed: u32, to: Address) {
+    let mut bounty = get_bounty(env);
+    if bounty.amount == 0 || removed == 0 {
+        return;
+    }
+
+    let token = get_dao_token_client(env);
+    let available = bounty
+        .fund
+        .min(token.balance(&env.current_contract_address()));
+    let amount = (removed as i128).min(available / bounty.amount) * bounty.amount;
+    if amount == 0 {
+        return;
+    }
+
+    bounty.fund -= amount;
+    env.storage().set(&DataKey::Bounty, &bounty);
+    token.xfer(&env.current_contract_address(), &to, &amount);
+}
+
+// delete the storage of a finalized proposal. Voters can be removed over several calls,
+// and each call pays the bounty for the voters it removed. Delegates that didn't vote can be
+// passed too, to remove what was recorded for them when their delegators voted.
+pub fn cleanup(env: &Env, prop_id: u32, voters: Vec<Address>, to: Address) {
+    if get_summary(env, prop_id).is_none() {
+        summarize(env, prop_id);
+    }
+
+    let mut removed = 0;
+    for voter in voters.iter() {
+        let voter = voter.unwrap_optimized();
+        let voted = DataKey::Voted(ProposalVoted {
+            voter: voter.clone(),
+            prop_id,
+        });
+        let found = env.storage().has(&voted);
+        env.storage().remove(&voted);
+
+        if remove_ballot(env, prop_id, voter) || found {
+            removed += 1;
+        }
+    }
+
+    pay_bounty(env, removed, to)
+}
@@ -0,0 +1,156 @@
+use soroban_sdk::{
+    contracttype, panic_with_error, symbol, unwrap::UnwrapOptimized, Address, Env, RawVal,
+    TryFromVal, Vec,
+};
+
+use crate::{
+    data_keys::DataKey,
+    errors::ContractError,
+    proposal::Proposal,
+    settings::now,
+    threshold::{FunKey, Threshold},
+    token::get_dao_token_id,
+};
+
+// a committee of members that can create proposals on behalf of the dao.
+// Every member has one vote.
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Committee {
+    pub members: Vec<Address>,
+    // approval and quorum in percent of the members
+    pub threshold: Threshold,
+    // dao tokens the committee can transfer out of the treasury per period
+    pub budget: i128,
+    // length of a budget period, in the units of the time model
+    pub period: u32,
+    // functions the committee can call besides spending from the treasury
+    pub fns: Vec<FunKey>,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct CommitteePeriod {
+    pub comm_id: u32,
+    pub period: u64,
+}
+
+// add committee and return its id
+pub fn add_committee(env: &Env, committee: Committee) -> u32 {
+    if committee.period == 0
+        || committee.budget < 0
+        || committee.threshold.approval > 100
+        || committee.threshold.quorum > 100
+    {
+        panic_with_error!(env, ContractError::InvalidCommittee)
+    }
+
+    let comm_id = env
+        .storage()
+        .get(&DataKey::CommId)
+        .unwrap_or(Ok(0u32))
+        .unwrap_optimized();
+    env.storage().set(&DataKey::CommId, &(comm_id + 1));
+
+    env.storage().set(&DataKey::Committee(comm_id), &committee);
+    comm_id
+}
+
+// revoke a committee. Its proposals can't be voted on or executed anymore.
+pub fn remove_committee(env: &Env, comm_id: u32) {
+    get_committee(env, comm_id);
+    env.storage().remove(&DataKey::Committee(comm_id))
+}
+
+pub fn get_committee(env: &Env, comm_id: u32) -> Committee {
+    find_committee(env, comm_id)
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::CommitteeRevoked))
+}
+
+// `None` if the committee was revoked
+pub fn find_committee(env: &Env, comm_id: u32) -> Option<Committee> {
+    env.storage()
+        .get(&DataKey::Committee(comm_id))
+        .map(|committee| committee.unwrap_optimized())
+}
+
+pub fn check_member(env: &Env, committee: &Committee, member: &Address) {
+    if !committee.members.contains(member) {
+        panic_with_error!(env, ContractError::NotCommitteeMember)
+    }
+}
+
+pub fn set_prop_committee(env: &Env, prop_id: u32, comm_id: u32) {
+    env.storage().set(&DataKey::PropComm(prop_id), &comm_id)
+}
+
+// the committee that created the proposal, if any
+pub fn get_prop_committee(env: &Env, prop_id: u32) -> Option<u32> {
+    env.storage()
+        .get(&DataKey::PropComm(prop_id))
+        .map(|comm_id| comm_id.unwrap_optimized())
+}
+
+// amount of dao tokens that `instr` transfers out of the treasury,
+// or `None` if it isn't a treasury transfer
+fn treasury_spend(env: &Env, fun: &FunKey, args: &Vec<RawVal>) -> Option<i128> {
+    if fun.c_id != get_dao_token_id(env) || fun.fun_name != symbol!("xfer") {
+        return None;
+    }
+
+    let from: Address = args
+        .get(0)
+        .and_then(|from| from.ok())
+        .and_then(|from| Address::try_from_val(env, &from).ok())?;
+    if from != env.current_contract_address() {
+        return None;
+    }
+
+    let amount = args
+        .get(2)
+        .and_then(|amount| amount.ok())
+        .and_then(|amount| i128::try_from_val(env, &amount).ok())
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::InvalidInstrArgs));
+    Some(amount)
+}
+
+// total amount that the proposal transfers out of the treasury.
+// Panics if the proposal does anything that the committee isn't allowed to do.
+pub fn check_committee_instrs(env: &Env, committee: &Committee, proposal: &Proposal) -> i128 {
+    let mut spend = 0;
+    for instr in proposal.instr.iter() {
+        let instr = instr.unwrap_optimized();
+        let fun = FunKey {
+            c_id: instr.c_id,
+            fun_name: instr.fun_name,
+        };
+
+        if let Some(amount) = treasury_spend(env, &fun, &instr.args) {
+            spend += amount;
+        } else if !committee.fns.contains(&fun) {
+            panic_with_error!(env, ContractError::CommitteeInstrNotAllowed)
+        }
+    }
+    spend
+}
+
+pub fn get_spent(env: &Env, comm_id: u32, period: u64) -> i128 {
+    env.storage()
+        .get(&DataKey::CommSpent(CommitteePeriod { comm_id, period }))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+// record that `amount` was spent in the current period
+pub fn spend_budget(env: &Env, comm_id: u32, committee: &Committee, amount: i128) {
+    let period = now(env) / (committee.period as u64);
+    let spent = get_spent(env, comm_id, period) + amount;
+    if spent > committee.budget {
+        panic_with_error!(env, ContractError::BudgetExceeded)
+    }
+
+    env.storage().set(
+        &DataKey::CommSpent(CommitteePeriod { comm_id, period }),
+        &spent,
+    )
+}
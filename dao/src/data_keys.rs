@@ -1,6 +1,8 @@
 use soroban_sdk::{contracttype, panic_with_error, Address, Env};
 
-use crate::{errors::ContractError, proposal::ProposalVoted};
+use crate::{
+    committee::CommitteePeriod, errors::ContractError, proposal::ProposalVoted, threshold::FunKey,
+};
 
 #[derive(Clone)]
 #[contracttype]
@@ -12,31 +14,85 @@ pub enum DataKey {
     Proposal(u32),
     //start ledger of proposal
     PropStart(u32),
+    // time after which the proposal can't be executed, fixed when it is created
+    // u64
+    PropExpiry(u32),
     // the next proposal id to use
     ProposalId,
+    // ids of proposals that are not executed or expired
+    // Vec<u32>
+    Active,
     // percentage of for votes + abstains needed to pass proposal
     // so a value of 75 would mean that 75% of voting power is needed to pass the propposal
     Quorum,
-    //Minimum duration of proposal in seconds
+    // whether deadlines are timestamps or ledger sequence numbers
+    TimeModel,
+    //Minimum duration of proposal, in the units of the time model
     MinTime,
+    //Maximum duration of proposal, in the units of the time model
+    MaxTime,
+    // time after the end of a proposal in which it can be executed
+    Grace,
+    // whether proposals can be executed before they end once the outcome is decided
+    EarlyExec,
     // if this person voted for this proposal
     Voted(ProposalVoted),
-    // abstain votes for this proposal
+    // -> VotesCount struct
+    Tally(u32),
+    // abstain votes for this proposal. Replaced by `Tally`, and removed on the next vote.
     AbstainV(u32),
-    // fo votes
+    // fo votes. Replaced by `Tally`, and removed on the next vote.
     ForVotes(u32),
-    // against votes
+    // against votes. Replaced by `Tally`, and removed on the next vote.
     AgainstV(u32),
     Nonce(Address),
     // min power to propose
     MinPropP,
     //whether a proposal has been executedd
     Executed(u32),
+    // token supply when the proposal was created
+    PropSupply(u32),
+    // approval and quorum needed for proposals that call this function
+    Threshold(FunKey),
+    // whether proposals can only call allowed functions
+    AllowMode,
+    // if proposals can call this function in allow mode
+    Allowed(FunKey),
+    // the next committee id to use
+    CommId,
+    // -> Committee struct
+    Committee(u32),
+    // id of the committee that created this proposal
+    PropComm(u32),
+    // amount a committee spent from the treasury in a period
+    CommSpent(CommitteePeriod),
+    // -> Guardian struct
+    Guardian,
+    // time at which the current or last pause ends
+    PauseEnd,
+    // -> PropSummary struct, of a proposal that was cleaned up
+    Summary(u32),
+    // -> Bounty struct
+    Bounty,
+    // -> Ballot struct, how this address voted
+    Ballot(ProposalVoted),
+    // power delegated to this address that was voted with by delegates below it
+    // i128
+    Claimed(ProposalVoted),
+    // -> Movable struct, votes that live delegators can still take back
+    Movable(u32),
 }
 
+// every entrypoint except `init` requires the dao to be initialized
 pub fn check_init(env: &Env) {
-    if is_init(&env) {
-        panic_with_error!(&env, ContractError::NotInit)
+    if !is_init(env) {
+        panic_with_error!(env, ContractError::NotInitialized)
+    }
+}
+
+pub fn check_not_init(env: &Env) {
+    if is_init(env) {
+        panic_with_error!(env, ContractError::AlreadyInitialized)
     }
 }
 
@@ -1,10 +1,10 @@
-use soroban_sdk::contracterror;
+use soroban_sdk::{contracterror, Status};
 
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
 pub enum ContractError {
-    NotInit = 0,
+    NotInitialized = 0,
     MinDurationNotSatisfied = 1,
     CannotAddNegativeVote = 2,
     InvalidNonce = 3,
@@ -14,5 +14,33 @@ pub enum ContractError {
     TooEarlyToExecute = 7,
     AllreadyExecuted = 8,
     ForVotesLessThanAgainstVotes = 9,
-    PropDeadlinePassed = 10
+    PropDeadlinePassed = 10,
+    MaxDurationExceeded = 11,
+    VotingNotStarted = 12,
+    NotEnoughApproval = 13,
+    QuorumNotReached = 14,
+    InvalidThreshold = 15,
+    InvalidInstrArgs = 16,
+    UnknownSetting = 17,
+    ProposalExpired = 18,
+    NotTokenAdmin = 20,
+    InvalidCommittee = 21,
+    CommitteeRevoked = 22,
+    NotCommitteeMember = 23,
+    CommitteeInstrNotAllowed = 24,
+    BudgetExceeded = 25,
+    NotGuardian = 26,
+    PauseCooldown = 27,
+    Paused = 28,
+    NotFinalized = 29,
+    InvalidBounty = 30,
+    AlreadyInitialized = 31,
+    InvalidDurations = 32,
+    // offset by the index of the proposal instruction that isn't allowed
+    InstrNotAllowed = 1000,
+}
+
+// error for the instruction at `index` of a proposal not being in the allowlist
+pub fn instr_not_allowed(index: u32) -> Status {
+    Status::from_contract_error(ContractError::InstrNotAllowed as u32 + index)
 }
@@ -1,23 +1,45 @@
 #![no_std]
 
+mod allowlist;
+mod ballot;
+mod cleanup;
+mod committee;
 mod data_keys;
 mod errors;
+mod pause;
 mod proposal;
 mod settings;
 mod test;
+mod threshold;
 mod token;
 
-use data_keys::{check_init, set_init};
+use allowlist::{allow_mode, check_allowed, is_allowed};
+use ballot::{cast_ballot, get_ballot, get_movable, vote_weight, Ballot, VoteSide};
+use cleanup::{cleanup, get_bounty, get_summary, Bounty, PropSummary};
+use committee::{
+    check_committee_instrs, check_member, get_committee, get_prop_committee, set_prop_committee,
+    spend_budget, Committee,
+};
+use data_keys::{check_init, check_not_init, set_init};
+use pause::{check_not_paused, get_pause_end, pause};
 use proposal::{
-    add_abstain_votes, add_against_votes, add_for_votes, add_proposal, check_min_duration,
-    check_min_prop_power, check_voted, get_against_votes, get_for_votes, get_min_proposal_power,
-    get_prop_start_ledger, get_proposal, set_min_proposal_power, set_voted, votes_counts, Proposal,
-    VotesCount,
+    add_abstain_votes, add_active, add_against_votes, add_for_votes, add_proposal,
+    check_max_duration, check_min_duration, check_min_prop_power, check_voted, get_expiry,
+    get_min_proposal_power, get_prop_count, get_prop_start_ledger, get_prop_supply, get_proposal,
+    prune_active, remove_active, set_min_proposal_power, set_prop_supply, set_voted,
+    unexpired_active, votes_counts, Proposal, VotesCount,
+};
+use settings::{
+    check_durations, exec_setting, get_early_exec, get_grace_period, get_max_prop_duration,
+    get_min_prop_duration, get_quorum, get_time_model, now, set_grace_period,
+    set_max_prop_duration, set_min_prop_duration, set_quorum, set_time_model, TimeModel,
 };
-use settings::{get_min_prop_duration, get_quorum, set_min_prop_duration, set_quorum};
 use soroban_sdk::{
-    assert_with_error, contractimpl, contracttype, panic_with_error, symbol, Address, BytesN, Env,
-    Symbol,
+    assert_with_error, contractimpl, contracttype, panic_with_error, Address, BytesN, Env, Symbol,
+    Vec,
+};
+use threshold::{
+    check_threshold, get_threshold, is_decided, proposal_threshold, FunKey, Threshold,
 };
 use token::{get_dao_token_client, store_dao_token};
 
@@ -31,23 +53,54 @@ use crate::{
 pub struct ProposalExtra {
     pub proposal: Proposal,
     pub start_seq: u32,
+    pub origin: PropOrigin,
+}
+
+// who created a proposal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PropOrigin {
+    // a member with enough voting power
+    Members,
+    // a committee with this id
+    Committee(u32),
 }
 pub trait DaoTrait {
+    #[allow(clippy::too_many_arguments)]
     fn init(
         env: Env,
         dao_token_id: BytesN<32>,
+        time_model: TimeModel,
         min_prop_duration: u32,
+        max_prop_duration: u32,
+        grace_period: u32,
         min_quorum_percent: u32,
         min_prop_power: i128,
     );
     //create proposal and return its id
     fn c_prop(env: Env, from: Address, proposal: Proposal) -> u32;
 
+    //create proposal on behalf of a committee that `from` is a member of and return its id
+    fn c_cprop(env: Env, from: Address, comm_id: u32, proposal: Proposal) -> u32;
+
     //try to execute prop
     fn execute(env: Env, prop_id: u32);
 
     fn proposal(env: Env, prop_id: u32) -> ProposalExtra;
 
+    // time after which a passed proposal can't be executed anymore
+    fn expiry(env: Env, prop_id: u32) -> u64;
+
+    // amount of proposals that have been created
+    fn prop_count(env: Env) -> u32;
+
+    // at most `limit` proposals, starting with proposal id `start`.
+    // Proposals that were cleaned up are skipped.
+    fn proposals(env: Env, start: u32, limit: u32) -> Vec<ProposalExtra>;
+
+    // ids of proposals that are not executed or expired
+    fn active(env: Env) -> Vec<u32>;
+
     //allow a member to vote on a proposal]
     fn vote_for(env: Env, from: Address, prop_id: u32);
     fn v_against(env: Env, from: Address, prop_id: u32);
@@ -55,82 +108,189 @@ pub trait DaoTrait {
 
     fn votes(env: Env, prop_id: u32) -> VotesCount;
 
+    // how `voter` voted with token power, and how much of it is still counted.
+    // A delegate's weight goes down when a delegator below it votes itself.
+    fn ballot(env: Env, prop_id: u32, voter: Address) -> Option<Ballot>;
+
+    // delete the proposal and the votes of `voters` once it was executed or expired.
+    // Anyone can call this, and it pays the cleanup bounty to `to` for every voter it removes.
+    fn cleanup(env: Env, prop_id: u32, voters: Vec<Address>, to: Address);
+
+    // what is kept of a proposal after it was cleaned up
+    fn summary(env: Env, prop_id: u32) -> PropSummary;
+
+    // bounty per voter removed by a cleanup and what is left to pay it from
+    fn bounty(env: Env) -> Bounty;
+
     //min power to propose
     fn min_prop_p(env: Env) -> i128;
+    // whether deadlines are timestamps or ledger sequence numbers
+    fn time_model(env: Env) -> TimeModel;
     // get minimum duration of proposal
     fn min_dur(env: Env) -> u32;
+    // get maximum duration of proposal
+    fn max_dur(env: Env) -> u32;
+    // get how long a proposal can be executed after it ended
+    fn grace(env: Env) -> u32;
     //minimum percentage to for proposal to pass.
-    // so for (votes + abstain / total_power) * 100 must be > quorum
+    // so for (votes + abstain / total_power) * 100 must be at least quorum
     fn quorum(env: Env) -> u32;
+    // whether proposals can be executed before they end once the outcome can't change anymore
+    fn early(env: Env) -> bool;
+
+    // approval and quorum needed for a proposal that calls `fun_name` on `c_id`
+    fn threshold(env: Env, c_id: BytesN<32>, fun_name: Symbol) -> Threshold;
+
+    // whether proposals can only call functions in the allowlist
+    fn al_mode(env: Env) -> bool;
+    // whether `fun_name` on `c_id` is in the allowlist
+    fn allowed(env: Env, c_id: BytesN<32>, fun_name: Symbol) -> bool;
+
+    // address of the dao, which holds the treasury and has to be the token admin.
+    // Can be called before `init`, so that the token admin can be set first.
+    fn address(env: Env) -> Address;
+
+    fn committee(env: Env, comm_id: u32) -> Committee;
+
+    // stop proposals from being created or executed until the pause expires.
+    // Only the guardian can pause, and a proposal can unpause.
+    fn pause(env: Env, guardian: Address);
+
+    // time at which the current or last pause ends
+    fn pause_end(env: Env) -> u64;
 }
 
 pub struct DaoContract;
 
 #[contractimpl]
 impl DaoTrait for DaoContract {
+    #[allow(clippy::too_many_arguments)]
     fn init(
         env: Env,
         dao_token_id: BytesN<32>,
+        time_model: TimeModel,
         min_prop_duration: u32,
+        max_prop_duration: u32,
+        grace_period: u32,
         min_quorum_percent: u32,
         min_prop_power: i128,
     ) {
-        check_init(&env);
-        // we need to be the dao token admin.
-        // just assume that we are, and if not that people check.
-        // can't see a way to check that I am an admin.
-        // todo, maybe i should make this contract the token admin in this function
+        check_not_init(&env);
+        check_durations(&env, min_prop_duration, max_prop_duration);
         store_dao_token(&env, dao_token_id);
+
+        // we need to be the dao token admin, otherwise proposals can't mint.
+        assert_with_error!(
+            &env,
+            get_dao_token_client(&env).admin() == env.current_contract_address(),
+            ContractError::NotTokenAdmin
+        );
         set_init(&env);
+        set_time_model(&env, time_model);
         set_min_prop_duration(&env, min_prop_duration);
+        set_max_prop_duration(&env, max_prop_duration);
+        set_grace_period(&env, grace_period);
         set_min_proposal_power(&env, min_prop_power);
         set_quorum(&env, min_quorum_percent);
     }
 
     fn c_prop(env: Env, from: Address, proposal: Proposal) -> u32 {
+        check_init(&env);
         // verify
         // verify nonce
 
+        check_not_paused(&env, &proposal);
+        check_min_duration(&env, &proposal);
+        check_max_duration(&env, &proposal);
+        check_allowed(&env, &proposal);
+        let client = get_dao_token_client(&env);
+        check_min_prop_power(&env, client.power(&from));
+
+        let prop_id = add_proposal(&env, proposal);
+        set_prop_supply(&env, prop_id, client.supply());
+
+        prune_active(&env);
+        add_active(&env, prop_id);
+        prop_id
+    }
+
+    fn c_cprop(env: Env, from: Address, comm_id: u32, proposal: Proposal) -> u32 {
+        check_init(&env);
+        let committee = get_committee(&env, comm_id);
+        check_member(&env, &committee, &from);
+        from.require_auth();
+
+        check_not_paused(&env, &proposal);
         check_min_duration(&env, &proposal);
-        check_min_prop_power(&env, get_dao_token_client(&env).power(&from));
-        //todo, store the token supply at this point
-        add_proposal(&env, proposal)
+        check_max_duration(&env, &proposal);
+        check_allowed(&env, &proposal);
+        check_committee_instrs(&env, &committee, &proposal);
+
+        let prop_id = add_proposal(&env, proposal);
+        // every member has one vote
+        set_prop_supply(&env, prop_id, committee.members.len() as i128);
+        set_prop_committee(&env, prop_id, comm_id);
+
+        prune_active(&env);
+        add_active(&env, prop_id);
+        prop_id
     }
 
     //try to execute prop
     fn execute(env: Env, prop_id: u32) {
+        check_init(&env);
         if executed(&env, prop_id) {
             panic_with_error!(env, ContractError::AllreadyExecuted)
         }
 
         let proposal = get_proposal(&env, prop_id);
+        check_not_paused(&env, &proposal);
+
+        let votes = votes_counts(&env, prop_id);
+        let supply = get_prop_supply(&env, prop_id);
+        // committee proposals use the rules and budget of the committee
+        let committee = get_prop_committee(&env, prop_id)
+            .map(|comm_id| (comm_id, get_committee(&env, comm_id)));
+        let threshold = match &committee {
+            Some((_, committee)) => committee.threshold.clone(),
+            None => proposal_threshold(&env, &proposal),
+        };
+
+        if now(&env) < proposal.end_time {
+            assert_with_error!(
+                &env,
+                get_early_exec(&env)
+                    && is_decided(&threshold, &votes, &get_movable(&env, prop_id), supply),
+                ContractError::TooEarlyToExecute
+            );
+        }
 
         assert_with_error!(
             &env,
-            proposal.end_time <= env.ledger().timestamp(),
-            ContractError::TooEarlyToExecute
+            now(&env) < get_expiry(&env, prop_id),
+            ContractError::ProposalExpired
         );
 
-        let for_votes = get_for_votes(&env, prop_id);
-
         assert_with_error!(
             &env,
-            for_votes > get_against_votes(&env, prop_id),
+            votes.v_for > votes.v_against,
             ContractError::ForVotesLessThanAgainstVotes
         );
 
-        // let token_client = get_dao_token_client(&env);
-        //
-        // assert_with_error!(
-        //     &env,
-        //     for_votes + get_abstain_votes(&env, prop_id) >  get_quorum(&env) * token_client.
-        // )
+        // the allowlist might have been enabled after the proposal was created
+        check_allowed(&env, &proposal);
+
+        check_threshold(&env, &threshold, &votes, supply);
+        if let Some((comm_id, committee)) = committee {
+            let spend = check_committee_instrs(&env, &committee, &proposal);
+            spend_budget(&env, comm_id, &committee, spend);
+        }
 
         for result in proposal.instr {
             match result {
                 Ok(instr) => {
                     if env.current_contract_id() == instr.c_id {
-                        //todo add stuff for settings
+                        exec_setting(&env, instr.fun_name, instr.args)
                     } else {
                         env.invoke_contract(&instr.c_id, &instr.fun_name, instr.args)
                     }
@@ -139,78 +299,225 @@ impl DaoTrait for DaoContract {
             }
         }
         set_executed(&env, prop_id);
+        remove_active(&env, prop_id);
+        prune_active(&env);
     }
 
     fn proposal(env: Env, prop_id: u32) -> ProposalExtra {
-        ProposalExtra {
-            proposal: get_proposal(&env, prop_id),
-            start_seq: get_prop_start_ledger(&env, prop_id),
+        check_init(&env);
+        proposal_extra(&env, prop_id)
+    }
+
+    fn expiry(env: Env, prop_id: u32) -> u64 {
+        check_init(&env);
+        get_expiry(&env, prop_id)
+    }
+
+    fn prop_count(env: Env) -> u32 {
+        check_init(&env);
+        get_prop_count(&env)
+    }
+
+    fn proposals(env: Env, start: u32, limit: u32) -> Vec<ProposalExtra> {
+        check_init(&env);
+        let end = get_prop_count(&env).min(start.saturating_add(limit));
+        let mut proposals = Vec::new(&env);
+        for prop_id in start..end {
+            if get_summary(&env, prop_id).is_none() {
+                proposals.push_back(proposal_extra(&env, prop_id))
+            }
         }
+        proposals
+    }
+
+    fn active(env: Env) -> Vec<u32> {
+        check_init(&env);
+        unexpired_active(&env)
     }
 
     //allow a member to vote on a proposal]
     fn vote_for(env: Env, from: Address, prop_id: u32) {
+        check_init(&env);
         add_for_votes(
             &env,
             prop_id,
-            vote_helper(&env, from, prop_id, symbol!("vote_for")),
+            vote_helper(&env, from, prop_id, VoteSide::For),
         );
     }
 
     fn v_against(env: Env, from: Address, prop_id: u32) {
+        check_init(&env);
         add_against_votes(
             &env,
             prop_id,
-            vote_helper(&env, from, prop_id, symbol!("v_against")),
+            vote_helper(&env, from, prop_id, VoteSide::Against),
         )
     }
 
     fn v_abstain(env: Env, from: Address, prop_id: u32) {
+        check_init(&env);
         add_abstain_votes(
             &env,
             prop_id,
-            vote_helper(&env, from, prop_id, symbol!("v_abstain")),
+            vote_helper(&env, from, prop_id, VoteSide::Abstain),
         )
     }
 
     fn votes(env: Env, prop_id: u32) -> VotesCount {
-        votes_counts(&env, prop_id)
+        check_init(&env);
+        match get_summary(&env, prop_id) {
+            Some(summary) => summary.votes,
+            None => votes_counts(&env, prop_id),
+        }
+    }
+
+    fn ballot(env: Env, prop_id: u32, voter: Address) -> Option<Ballot> {
+        check_init(&env);
+        get_ballot(&env, prop_id, voter)
+    }
+
+    fn cleanup(env: Env, prop_id: u32, voters: Vec<Address>, to: Address) {
+        check_init(&env);
+        cleanup(&env, prop_id, voters, to)
+    }
+
+    fn summary(env: Env, prop_id: u32) -> PropSummary {
+        check_init(&env);
+        get_summary(&env, prop_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ContractError::InvalidProposalId))
+    }
+
+    fn bounty(env: Env) -> Bounty {
+        check_init(&env);
+        get_bounty(&env)
+    }
+
+    fn time_model(env: Env) -> TimeModel {
+        check_init(&env);
+        get_time_model(&env)
     }
 
     fn min_dur(env: Env) -> u32 {
+        check_init(&env);
         get_min_prop_duration(&env)
     }
 
+    fn max_dur(env: Env) -> u32 {
+        check_init(&env);
+        get_max_prop_duration(&env)
+    }
+
+    fn grace(env: Env) -> u32 {
+        check_init(&env);
+        get_grace_period(&env)
+    }
+
     fn quorum(env: Env) -> u32 {
+        check_init(&env);
         get_quorum(&env)
     }
 
+    fn early(env: Env) -> bool {
+        check_init(&env);
+        get_early_exec(&env)
+    }
+
     fn min_prop_p(env: Env) -> i128 {
+        check_init(&env);
         get_min_proposal_power(&env)
     }
+
+    fn threshold(env: Env, c_id: BytesN<32>, fun_name: Symbol) -> Threshold {
+        check_init(&env);
+        get_threshold(&env, FunKey { c_id, fun_name })
+    }
+
+    fn al_mode(env: Env) -> bool {
+        check_init(&env);
+        allow_mode(&env)
+    }
+
+    fn allowed(env: Env, c_id: BytesN<32>, fun_name: Symbol) -> bool {
+        check_init(&env);
+        is_allowed(&env, FunKey { c_id, fun_name })
+    }
+
+    fn address(env: Env) -> Address {
+        env.current_contract_address()
+    }
+
+    fn committee(env: Env, comm_id: u32) -> Committee {
+        check_init(&env);
+        get_committee(&env, comm_id)
+    }
+
+    fn pause(env: Env, guardian: Address) {
+        check_init(&env);
+        pause(&env, guardian)
+    }
+
+    fn pause_end(env: Env) -> u64 {
+        check_init(&env);
+        get_pause_end(&env)
+    }
+}
+
+fn proposal_extra(env: &Env, prop_id: u32) -> ProposalExtra {
+    ProposalExtra {
+        proposal: get_proposal(env, prop_id),
+        start_seq: get_prop_start_ledger(env, prop_id),
+        origin: match get_prop_committee(env, prop_id) {
+            Some(comm_id) => PropOrigin::Committee(comm_id),
+            None => PropOrigin::Members,
+        },
+    }
 }
 
 // function to avoid code duplication in the vote functions
 
-fn vote_helper(env: &Env, from: Address, prop_id: u32, symbol: Symbol) -> i128 {
+fn vote_helper(env: &Env, from: Address, prop_id: u32, side: VoteSide) -> i128 {
     let client = get_dao_token_client(&env);
     let start_ledger = get_prop_start_ledger(&env, prop_id);
 
     // check if person allready voted
     check_voted(&env, prop_id, from.clone());
     
+    // votes are only accepted after the ledger in which the proposal was created,
+    // so that the power snapshot at `start_ledger` can no longer change.
+    assert_with_error!(
+        &env,
+        env.ledger().sequence() > start_ledger,
+        ContractError::VotingNotStarted
+    );
+
     let prop = get_proposal(&env, prop_id);
     assert_with_error!(
         &env,
-        prop.end_time <= env.ledger().timestamp(),
+        now(env) < prop.end_time,
         ContractError::PropDeadlinePassed
     );
 
-    let power_at_start = client.power_at(&from, &start_ledger);
+    let committee = get_prop_committee(env, prop_id);
+    let weight = match committee {
+        Some(comm_id) => {
+            check_member(env, &get_committee(env, comm_id), &from);
+            1
+        }
+        None => vote_weight(
+            env,
+            prop_id,
+            from.clone(),
+            client.power_at(&from, &start_ledger),
+        ),
+    };
 
     from.require_auth();
 
     set_voted(&env, prop_id, from.clone());
+    // committee members don't vote with delegated power
+    if committee.is_none() {
+        cast_ballot(env, prop_id, from, start_ledger, Ballot { side, weight });
+    }
 
-    power_at_start
+    weight
 }
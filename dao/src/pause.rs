@@ -0,0 +1,78 @@
+use soroban_sdk::{contracttype, panic_with_error, symbol, unwrap::UnwrapOptimized, Address, Env};
+
+use crate::{data_keys::DataKey, errors::ContractError, proposal::Proposal, settings::now};
+
+// address that can pause the dao, and for how long
+#[contracttype]
+#[derive(Clone)]
+pub struct Guardian {
+    pub address: Address,
+    // how long a pause lasts, in the units of the time model.
+    // Also the time after a pause ended before the next one.
+    pub duration: u32,
+}
+
+pub fn set_guardian(env: &Env, guardian: Guardian) {
+    env.storage().set(&DataKey::Guardian, &guardian)
+}
+
+pub fn remove_guardian(env: &Env) {
+    env.storage().remove(&DataKey::Guardian)
+}
+
+fn get_guardian(env: &Env) -> Guardian {
+    env.storage()
+        .get(&DataKey::Guardian)
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::NotGuardian))
+        .unwrap_optimized()
+}
+
+// time at which the current or last pause ends
+pub fn get_pause_end(env: &Env) -> u64 {
+    env.storage()
+        .get(&DataKey::PauseEnd)
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+pub fn is_paused(env: &Env) -> bool {
+    now(env) < get_pause_end(env)
+}
+
+pub fn pause(env: &Env, guardian: Address) {
+    let stored = get_guardian(env);
+    if stored.address != guardian {
+        panic_with_error!(env, ContractError::NotGuardian)
+    }
+    guardian.require_auth();
+
+    // pauses can't be extended or chained, so the guardian can't hold the dao hostage
+    let now = now(env);
+    if now < get_pause_end(env) + (stored.duration as u64) {
+        panic_with_error!(env, ContractError::PauseCooldown)
+    }
+
+    let end = now + (stored.duration as u64);
+    env.storage().set(&DataKey::PauseEnd, &end);
+    env.events().publish((symbol!("pause"), guardian), end);
+}
+
+pub fn unpause(env: &Env) {
+    let now = now(env);
+    env.storage().set(&DataKey::PauseEnd, &now);
+    env.events().publish((symbol!("unpause"),), now);
+}
+
+// proposals that only unpause the dao can be created and executed while it is paused
+pub fn check_not_paused(env: &Env, proposal: &Proposal) {
+    if !is_paused(env) {
+        return;
+    }
+
+    for instr in proposal.instr.iter() {
+        let instr = instr.unwrap_optimized();
+        if instr.c_id != env.current_contract_id() || instr.fun_name != symbol!("unpause") {
+            panic_with_error!(env, ContractError::Paused)
+        }
+    }
+}
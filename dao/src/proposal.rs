@@ -3,7 +3,12 @@ use soroban_sdk::{
     Vec,
 };
 
-use crate::{data_keys::DataKey, errors::ContractError, settings::get_min_prop_duration};
+use crate::{
+    ballot::VoteSide,
+    data_keys::DataKey,
+    errors::ContractError,
+    settings::{get_grace_period, get_max_prop_duration, get_min_prop_duration, now},
+};
 
 #[contracttype]
 #[derive(Clone, Debug)]
@@ -24,6 +29,7 @@ pub struct ProposalInstr {
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct Proposal {
+    // end of voting, as a timestamp or ledger sequence depending on the time model
     pub end_time: u64,
     // instrunctions will be executed in sequence
     pub instr: Vec<ProposalInstr>,
@@ -35,17 +41,33 @@ pub struct VotesCount {
     pub v_for: i128,
     pub v_against: i128,
     pub v_abstain: i128,
+    // amount of members that voted
+    pub voters: u32,
 }
 // add prop and return its id
 pub fn add_proposal(env: &Env, proposal: Proposal) -> u32 {
     let prop_id = get_and_inc_prop_id(env);
 
+    let expiry = proposal.end_time + (get_grace_period(env) as u64);
     env.storage().set(&DataKey::Proposal(prop_id), &proposal);
+    env.storage().set(&DataKey::PropExpiry(prop_id), &expiry);
+    env.storage().set(&DataKey::Tally(prop_id), &empty_votes());
     set_prop_start_ledger(env, prop_id, env.ledger().sequence());
 
     prop_id
 }
 
+pub fn set_prop_supply(env: &Env, prop_id: u32, supply: i128) {
+    env.storage().set(&DataKey::PropSupply(prop_id), &supply)
+}
+
+pub fn get_prop_supply(env: &Env, prop_id: u32) -> i128 {
+    env.storage()
+        .get(&DataKey::PropSupply(prop_id))
+        .unwrap_optimized()
+        .unwrap_optimized()
+}
+
 pub fn get_proposal(env: &Env, prop_id: u32) -> Proposal {
     env.storage()
         .get(&DataKey::Proposal(prop_id))
@@ -53,24 +75,91 @@ pub fn get_proposal(env: &Env, prop_id: u32) -> Proposal {
         .unwrap_optimized()
 }
 
-fn get_and_inc_prop_id(env: &Env) -> u32 {
-    let prev = env
-        .storage()
+// amount of proposals that have been created
+pub fn get_prop_count(env: &Env) -> u32 {
+    env.storage()
         .get(&DataKey::ProposalId)
         .unwrap_or(Ok(0u32))
-        .unwrap_optimized();
+        .unwrap_optimized()
+}
+
+fn get_and_inc_prop_id(env: &Env) -> u32 {
+    let prev = get_prop_count(env);
 
     env.storage().set(&DataKey::ProposalId, &(prev + 1));
     prev
 }
 
+pub fn get_active(env: &Env) -> Vec<u32> {
+    env.storage()
+        .get(&DataKey::Active)
+        .unwrap_or(Ok(Vec::new(env)))
+        .unwrap_optimized()
+}
+
+fn set_active(env: &Env, active: &Vec<u32>) {
+    env.storage().set(&DataKey::Active, active)
+}
+
+pub fn add_active(env: &Env, prop_id: u32) {
+    let mut active = get_active(env);
+    active.push_back(prop_id);
+    set_active(env, &active)
+}
+
+pub fn remove_active(env: &Env, prop_id: u32) {
+    let mut active = get_active(env);
+    if let Some(index) = active.first_index_of(prop_id) {
+        active.remove(index);
+        set_active(env, &active)
+    }
+}
+
+// active proposals that have not expired yet
+pub fn unexpired_active(env: &Env) -> Vec<u32> {
+    let mut unexpired = Vec::new(env);
+    for prop_id in get_active(env).iter() {
+        let prop_id = prop_id.unwrap_optimized();
+        if now(env) < get_expiry(env, prop_id) {
+            unexpired.push_back(prop_id)
+        }
+    }
+    unexpired
+}
+
+// drop expired proposals from the active index
+pub fn prune_active(env: &Env) {
+    let unexpired = unexpired_active(env);
+    if unexpired.len() != get_active(env).len() {
+        set_active(env, &unexpired)
+    }
+}
+
 pub fn check_min_duration(env: &Env, proposal: &Proposal) {
     let min_duration = get_min_prop_duration(env);
-    if proposal.end_time - env.ledger().timestamp() < (min_duration as u64) {
+    // saturate so that an end time in the past is reported instead of underflowing
+    if proposal.end_time.saturating_sub(now(env)) < (min_duration as u64) {
         panic_with_error!(env, ContractError::MinDurationNotSatisfied)
     }
 }
 
+pub fn check_max_duration(env: &Env, proposal: &Proposal) {
+    let max_duration = get_max_prop_duration(env);
+    if proposal.end_time.saturating_sub(now(env)) > (max_duration as u64) {
+        panic_with_error!(env, ContractError::MaxDurationExceeded)
+    }
+}
+
+// time after which the proposal can't be executed anymore.
+// Uses the grace period from when the proposal was created, so changing it doesn't bring
+// expired proposals back. Proposals from before that was stored use the current one.
+pub fn get_expiry(env: &Env, prop_id: u32) -> u64 {
+    match env.storage().get(&DataKey::PropExpiry(prop_id)) {
+        Some(expiry) => expiry.unwrap_optimized(),
+        None => get_proposal(env, prop_id).end_time + (get_grace_period(env) as u64),
+    }
+}
+
 pub fn set_voted(env: &Env, prop_id: u32, voter: Address) {
     env.storage()
         .set(&DataKey::Voted(ProposalVoted { voter, prop_id }), &true)
@@ -97,68 +186,103 @@ pub fn set_prop_start_ledger(env: &Env, prop_id: u32, start_ledger: u32) {
 pub fn get_prop_start_ledger(env: &Env, prop_id: u32) -> u32 {
     env.storage()
         .get(&DataKey::PropStart(prop_id))
-        .unwrap_optimized()
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::InvalidProposalId))
         .unwrap_optimized()
 }
 
-pub fn get_for_votes(env: &Env, prop_id: u32) -> i128 {
-    env.storage()
-        .get(&DataKey::ForVotes(prop_id))
-        .unwrap_or(Ok(0))
-        .unwrap_optimized()
+// votes of the proposal so far
+pub fn votes_counts(env: &Env, prop_id: u32) -> VotesCount {
+    load_votes(env, prop_id).0
 }
 
-fn set_for_votes(env: &Env, prop_id: u32, amount: i128) {
-    env.storage().set(&DataKey::ForVotes(prop_id), &amount)
+fn empty_votes() -> VotesCount {
+    VotesCount {
+        v_for: 0,
+        v_against: 0,
+        v_abstain: 0,
+        voters: 0,
+    }
 }
 
-pub fn add_for_votes(env: &Env, prop_id: u32, amount: i128) {
-    if amount < 0 {
-        panic_with_error!(env, ContractError::CannotAddNegativeVote)
+// also returns whether the votes are still in `ForVotes`, `AgainstV` and `AbstainV`.
+// Proposals created before the `Tally` entry have them there until the next vote.
+// The amount of voters wasn't stored before, so it only counts the new voters.
+fn load_votes(env: &Env, prop_id: u32) -> (VotesCount, bool) {
+    match env.storage().get(&DataKey::Tally(prop_id)) {
+        Some(votes) => (votes.unwrap_optimized(), false),
+        None => (
+            VotesCount {
+                v_for: legacy_votes(env, DataKey::ForVotes(prop_id)),
+                v_against: legacy_votes(env, DataKey::AgainstV(prop_id)),
+                v_abstain: legacy_votes(env, DataKey::AbstainV(prop_id)),
+                voters: 0,
+            },
+            true,
+        ),
     }
+}
 
-    let curr_votes = get_for_votes(env, prop_id);
-    set_for_votes(env, prop_id, curr_votes + amount)
+fn legacy_votes(env: &Env, key: DataKey) -> i128 {
+    env.storage().get(&key).unwrap_or(Ok(0)).unwrap_optimized()
 }
 
-pub fn get_against_votes(env: &Env, prop_id: u32) -> i128 {
-    env.storage()
-        .get(&DataKey::AgainstV(prop_id))
-        .unwrap_or(Ok(0))
-        .unwrap_optimized()
+// `legacy` -> whether the votes were loaded from the old entries, which can go now
+fn save_votes(env: &Env, prop_id: u32, votes: &VotesCount, legacy: bool) {
+    if legacy {
+        remove_legacy_votes(env, prop_id);
+    }
+    env.storage().set(&DataKey::Tally(prop_id), votes)
 }
 
-fn set_against_votes(env: &Env, prop_id: u32, amount: i128) {
-    env.storage().set(&DataKey::AgainstV(prop_id), &amount)
+pub fn remove_votes_counts(env: &Env, prop_id: u32) {
+    remove_legacy_votes(env, prop_id);
+    env.storage().remove(&DataKey::Tally(prop_id))
 }
 
-pub fn add_against_votes(env: &Env, prop_id: u32, amount: i128) {
+fn remove_legacy_votes(env: &Env, prop_id: u32) {
+    env.storage().remove(&DataKey::ForVotes(prop_id));
+    env.storage().remove(&DataKey::AgainstV(prop_id));
+    env.storage().remove(&DataKey::AbstainV(prop_id));
+}
+
+// counts a new voter and returns the tally that the vote should be added to
+fn new_vote(env: &Env, prop_id: u32, amount: i128) -> (VotesCount, bool) {
     if amount < 0 {
         panic_with_error!(env, ContractError::CannotAddNegativeVote)
     }
 
-    let curr_votes = get_against_votes(env, prop_id);
-    set_against_votes(env, prop_id, curr_votes + amount)
+    let (mut votes, legacy) = load_votes(env, prop_id);
+    votes.voters += 1;
+    (votes, legacy)
 }
 
-pub fn get_abstain_votes(env: &Env, prop_id: u32) -> i128 {
-    env.storage()
-        .get(&DataKey::AbstainV(prop_id))
-        .unwrap_or(Ok(0))
-        .unwrap_optimized()
+pub fn add_for_votes(env: &Env, prop_id: u32, amount: i128) {
+    let (mut votes, legacy) = new_vote(env, prop_id, amount);
+    votes.v_for += amount;
+    save_votes(env, prop_id, &votes, legacy)
 }
 
-fn set_abstain_votes(env: &Env, prop_id: u32, amount: i128) {
-    env.storage().set(&DataKey::AbstainV(prop_id), &amount)
+pub fn add_against_votes(env: &Env, prop_id: u32, amount: i128) {
+    let (mut votes, legacy) = new_vote(env, prop_id, amount);
+    votes.v_against += amount;
+    save_votes(env, prop_id, &votes, legacy)
 }
 
 pub fn add_abstain_votes(env: &Env, prop_id: u32, amount: i128) {
-    if amount < 0 {
-        panic_with_error!(env, ContractError::CannotAddNegativeVote)
-    }
+    let (mut votes, legacy) = new_vote(env, prop_id, amount);
+    votes.v_abstain += amount;
+    save_votes(env, prop_id, &votes, legacy)
+}
 
-    let curr_votes = get_abstain_votes(env, prop_id);
-    set_abstain_votes(env, prop_id, curr_votes + amount)
+// takes back votes that a delegate cast for power which was then voted with directly
+pub fn remove_votes(env: &Env, prop_id: u32, side: VoteSide, amount: i128) {
+    let (mut votes, legacy) = load_votes(env, prop_id);
+    match side {
+        VoteSide::For => votes.v_for -= amount,
+        VoteSide::Against => votes.v_against -= amount,
+        VoteSide::Abstain => votes.v_abstain -= amount,
+    }
+    save_votes(env, prop_id, &votes, legacy)
 }
 
 pub fn set_min_proposal_power(env: &Env, min_power: i128) {
@@ -178,18 +302,6 @@ pub fn check_min_prop_power(env: &Env, power: i128) {
     }
 }
 
-pub fn votes_counts(env: &Env, prop_id: u32) -> VotesCount {
-    let for_votes = get_for_votes(&env, prop_id);
-    let against_votes = get_against_votes(&env, prop_id);
-    let abstain_votes = get_abstain_votes(&env, prop_id);
-
-    VotesCount {
-        v_for: for_votes,
-        v_against: against_votes,
-        v_abstain: abstain_votes,
-    }
-}
-
 pub fn set_executed(env: &Env, prop_id: u32) {
     env.storage().set(&DataKey::Executed(prop_id), &true)
 }
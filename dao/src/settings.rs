@@ -1,6 +1,48 @@
-use soroban_sdk::{unwrap::UnwrapOptimized, Env};
+use soroban_sdk::{
+    contracttype, panic_with_error, symbol, unwrap::UnwrapOptimized, Env, RawVal, Symbol,
+    TryFromVal, Vec,
+};
 
-use crate::data_keys::DataKey;
+use crate::{
+    allowlist::{allow_fn, disallow_fn, set_allow_mode},
+    cleanup::{set_bounty, Bounty},
+    committee::{add_committee, remove_committee},
+    data_keys::DataKey,
+    errors::ContractError,
+    pause::{remove_guardian, set_guardian, unpause},
+    proposal::set_min_proposal_power,
+    threshold::{remove_threshold, set_threshold, FunKey, Threshold},
+};
+
+// what proposal deadlines and durations are measured in. Chosen at init.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimeModel {
+    // ledger timestamps, in seconds
+    Timestamp,
+    // ledger sequence numbers
+    Ledger,
+}
+
+pub fn set_time_model(env: &Env, model: TimeModel) {
+    env.storage().set(&DataKey::TimeModel, &model)
+}
+
+// daos that were initialized before the time model was added use timestamps
+pub fn get_time_model(env: &Env) -> TimeModel {
+    env.storage()
+        .get(&DataKey::TimeModel)
+        .unwrap_or(Ok(TimeModel::Timestamp))
+        .unwrap_optimized()
+}
+
+// current time in the units of the time model
+pub fn now(env: &Env) -> u64 {
+    match get_time_model(env) {
+        TimeModel::Timestamp => env.ledger().timestamp(),
+        TimeModel::Ledger => env.ledger().sequence() as u64,
+    }
+}
 
 // `percent` -> percent of quorum needed to pass proposal.
 // from 0 to 100
@@ -15,9 +57,9 @@ pub fn get_quorum(env: &Env) -> u32 {
         .unwrap_optimized()
 }
 
-// set min duration of proposal
-pub fn set_min_prop_duration(env: &Env, min_time_seconds: u32) {
-    env.storage().set(&DataKey::MinTime, &min_time_seconds)
+// set min duration of proposal, in the units of the time model
+pub fn set_min_prop_duration(env: &Env, min_duration: u32) {
+    env.storage().set(&DataKey::MinTime, &min_duration)
 }
 
 pub fn get_min_prop_duration(env: &Env) -> u32 {
@@ -26,3 +68,137 @@ pub fn get_min_prop_duration(env: &Env) -> u32 {
         .unwrap_optimized()
         .unwrap_optimized()
 }
+
+// set max duration of proposal, in the units of the time model
+pub fn set_max_prop_duration(env: &Env, max_duration: u32) {
+    env.storage().set(&DataKey::MaxTime, &max_duration)
+}
+
+pub fn get_max_prop_duration(env: &Env) -> u32 {
+    env.storage()
+        .get(&DataKey::MaxTime)
+        .unwrap_optimized()
+        .unwrap_optimized()
+}
+
+// proposals can't be created at all if the min duration is above the max
+pub fn check_durations(env: &Env, min_duration: u32, max_duration: u32) {
+    if min_duration > max_duration {
+        panic_with_error!(env, ContractError::InvalidDurations)
+    }
+}
+
+// set how long a proposal can be executed after it ended
+pub fn set_grace_period(env: &Env, grace_period: u32) {
+    env.storage().set(&DataKey::Grace, &grace_period)
+}
+
+pub fn get_grace_period(env: &Env) -> u32 {
+    env.storage()
+        .get(&DataKey::Grace)
+        .unwrap_optimized()
+        .unwrap_optimized()
+}
+
+// allow executing a proposal before it ends, if the votes that are left can't change the outcome
+pub fn set_early_exec(env: &Env, enabled: bool) {
+    env.storage().set(&DataKey::EarlyExec, &enabled)
+}
+
+pub fn get_early_exec(env: &Env) -> bool {
+    env.storage()
+        .get(&DataKey::EarlyExec)
+        .unwrap_or(Ok(false))
+        .unwrap_optimized()
+}
+
+// get argument `index` of a proposal instruction
+fn arg<T: TryFromVal<Env, RawVal>>(env: &Env, args: &Vec<RawVal>, index: u32) -> T {
+    let raw = args
+        .get(index)
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::InvalidInstrArgs))
+        .unwrap_optimized();
+
+    T::try_from_val(env, &raw)
+        .unwrap_or_else(|_| panic_with_error!(env, ContractError::InvalidInstrArgs))
+}
+
+// execute a proposal instruction that targets the dao itself.
+// The dao can't invoke itself, so settings are changed here instead.
+pub fn exec_setting(env: &Env, fun_name: Symbol, args: Vec<RawVal>) {
+    if fun_name == symbol!("set_quorum") {
+        set_quorum(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("set_mindur") {
+        let min_duration = arg(env, &args, 0);
+        check_durations(env, min_duration, get_max_prop_duration(env));
+        set_min_prop_duration(env, min_duration)
+    } else if fun_name == symbol!("set_maxdur") {
+        let max_duration = arg(env, &args, 0);
+        check_durations(env, get_min_prop_duration(env), max_duration);
+        set_max_prop_duration(env, max_duration)
+    } else if fun_name == symbol!("set_grace") {
+        set_grace_period(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("set_early") {
+        set_early_exec(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("set_min_p") {
+        set_min_proposal_power(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("set_thresh") {
+        set_threshold(
+            env,
+            FunKey {
+                c_id: arg(env, &args, 0),
+                fun_name: arg(env, &args, 1),
+            },
+            Threshold {
+                approval: arg(env, &args, 2),
+                quorum: arg(env, &args, 3),
+            },
+        )
+    } else if fun_name == symbol!("rm_thresh") {
+        remove_threshold(
+            env,
+            FunKey {
+                c_id: arg(env, &args, 0),
+                fun_name: arg(env, &args, 1),
+            },
+        )
+    } else if fun_name == symbol!("set_almode") {
+        set_allow_mode(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("allow_fn") {
+        allow_fn(
+            env,
+            FunKey {
+                c_id: arg(env, &args, 0),
+                fun_name: arg(env, &args, 1),
+            },
+        )
+    } else if fun_name == symbol!("disallow") {
+        disallow_fn(
+            env,
+            FunKey {
+                c_id: arg(env, &args, 0),
+                fun_name: arg(env, &args, 1),
+            },
+        )
+    } else if fun_name == symbol!("add_comm") {
+        add_committee(env, arg(env, &args, 0));
+    } else if fun_name == symbol!("rm_comm") {
+        remove_committee(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("set_guard") {
+        set_guardian(env, arg(env, &args, 0))
+    } else if fun_name == symbol!("rm_guard") {
+        remove_guardian(env)
+    } else if fun_name == symbol!("unpause") {
+        unpause(env)
+    } else if fun_name == symbol!("set_bounty") {
+        set_bounty(
+            env,
+            Bounty {
+                amount: arg(env, &args, 0),
+                fund: arg(env, &args, 1),
+            },
+        )
+    } else {
+        panic_with_error!(env, ContractError::UnknownSetting)
+    }
+}
@@ -2,11 +2,117 @@
 
 extern crate std;
 
-use crate::proposal::{Proposal, ProposalInstr};
+use crate::ballot::{Ballot, VoteSide};
+use crate::proposal::{Proposal, ProposalInstr, ProposalVoted};
 use crate::token::tokenclient;
-use crate::{DaoContract, DaoContractClient};
-use soroban_sdk::testutils::{Ledger, LedgerInfo, Address as _};
-use soroban_sdk::{symbol, vec, Bytes, Env, IntoVal, Address};
+use crate::cleanup::{Bounty, PropOutcome};
+use crate::committee::Committee;
+use crate::data_keys::DataKey;
+use crate::errors::ContractError;
+use crate::pause::Guardian;
+use crate::settings::TimeModel;
+use crate::threshold::Threshold;
+use crate::{DaoContract, DaoContractClient, PropOrigin};
+use soroban_sdk::testutils::{Events, Ledger, LedgerInfo, Address as _};
+use soroban_sdk::serde::Serialize;
+use soroban_sdk::{symbol, vec, Bytes, BytesN, Env, IntoVal, Address, RawVal, Status, Symbol, TryFromVal};
+
+fn set_ledger(env: &Env, timestamp: u64, sequence_number: u32) {
+    env.ledger().set(LedgerInfo {
+        timestamp,
+        protocol_version: 1,
+        sequence_number,
+        base_reserve: 1,
+        network_id: Default::default()
+    });
+}
+
+// deploys a token and a dao that is its admin, and gives every voter their power
+fn setup_dao(env: &Env, voters: &[(&Address, i128)]) -> (tokenclient::Client, DaoContractClient) {
+    setup_dao_with_model(env, voters, TimeModel::Timestamp)
+}
+
+fn setup_dao_with_model(
+    env: &Env,
+    voters: &[(&Address, i128)],
+    time_model: TimeModel,
+) -> (tokenclient::Client, DaoContractClient) {
+    let token_contract_id = env.register_contract_wasm(None, tokenclient::WASM);
+    let dao_contract_id = env.register_contract(None, DaoContract);
+    let admin = Address::random(env);
+
+    let token_client = tokenclient::Client::new(env, &token_contract_id);
+    token_client.initialize(
+        &admin,
+        &7,
+        &Bytes::from_array(env, b"DAO TOKEN"),
+        &Bytes::from_array(env, b"DTOKEN"),
+    );
+    for (voter, power) in voters {
+        token_client.mint(&admin, voter, power);
+        token_client.delegate(voter, voter, power);
+    }
+    token_client.set_admin(&admin, &Address::from_contract_id(env, &dao_contract_id));
+
+    let dao_client = DaoContractClient::new(env, &dao_contract_id);
+    dao_client.init(&token_contract_id, &time_model, &10, &100, &100, &0, &10);
+
+    // the cpu budget is shared by all the calls of a test, so the setup doesn't count
+    env.budget().reset();
+    (token_client, dao_client)
+}
+
+// create a proposal, vote for it with `voter` and execute it once it ended
+fn pass_proposal(
+    env: &Env,
+    dao_client: &DaoContractClient,
+    voter: &Address,
+    instr: soroban_sdk::Vec<ProposalInstr>,
+) {
+    let (now, seq) = (env.ledger().timestamp(), env.ledger().sequence());
+    let prop_id = dao_client.c_prop(voter, &Proposal { end_time: now + 50, instr });
+    set_ledger(env, now + 10, seq + 1);
+    dao_client.vote_for(voter, &prop_id);
+    set_ledger(env, now + 50, seq + 2);
+    dao_client.execute(&prop_id);
+}
+
+fn setting_instr(
+    dao_id: &BytesN<32>,
+    fun_name: Symbol,
+    args: soroban_sdk::Vec<RawVal>,
+) -> ProposalInstr {
+    ProposalInstr {
+        c_id: dao_id.clone(),
+        fun_name,
+        args,
+    }
+}
+
+fn mint_prop(
+    env: &Env,
+    token_id: &BytesN<32>,
+    dao_id: &BytesN<32>,
+    to: &Address,
+    end_time: u64,
+) -> Proposal {
+    Proposal {
+        end_time,
+        instr: vec![
+            env,
+            ProposalInstr {
+                c_id: token_id.clone(),
+                fun_name: symbol!("mint"),
+                args: vec![
+                    env,
+                    Address::from_contract_id(env, dao_id).into_val(env),
+                    to.into_val(env),
+                    (100i128.into_val(env)),
+                ],
+            },
+        ],
+    }
+}
 
 
 #[test]
@@ -97,7 +203,7 @@ fn test() {
         &Address::from_contract_id(&env, &dao_contract_id),
     );
 
-    dao_client.init(&token_contract_id, &1, &0, &10);
+    dao_client.init(&token_contract_id, &TimeModel::Timestamp, &1, &100, &100, &0, &10);
 
     let prop = Proposal {
         end_time: env.ledger().timestamp() + 10,
@@ -122,21 +228,1052 @@ fn test() {
     );
 
     env.ledger().set(LedgerInfo {
-        timestamp: env.ledger().timestamp() + 11,
+        timestamp: env.ledger().timestamp() + 1,
         protocol_version: 1,
-        sequence_number: 10,
+        sequence_number: 3,
         base_reserve: 1,
         network_id: Default::default()
     });
 
-    //todo shouldn't be able to vote after end time
     dao_client.vote_for(
         &user_2,
         &prop_id,
     );
 
+    env.ledger().set(LedgerInfo {
+        timestamp: env.ledger().timestamp() + 10,
+        protocol_version: 1,
+        sequence_number: 10,
+        base_reserve: 1,
+        network_id: Default::default()
+    });
+
     dao_client.execute(&prop_id);
 
     assert_eq!(190, token_client.balance(&user_2.clone().into()));
 
 }
+
+#[test]
+fn test_voting_window() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    set_ledger(&env, 1000, 1);
+    let prop = mint_prop(&env, &token_id, &dao_id, &voter, 1050);
+    let prop_id = dao_client.c_prop(&voter, &prop);
+
+    // can't vote in the ledger in which the proposal was created
+    assert_eq!(
+        dao_client.try_vote_for(&voter, &prop_id),
+        Err(Ok(ContractError::VotingNotStarted.into()))
+    );
+
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&voter, &prop_id);
+
+    // can't vote twice
+    assert_eq!(
+        dao_client.try_v_against(&voter, &prop_id),
+        Err(Ok(ContractError::AlreadyVoted.into()))
+    );
+    assert_eq!(100, dao_client.votes(&prop_id).v_for);
+    assert_eq!(0, dao_client.votes(&prop_id).v_against);
+
+    // can't vote once the deadline is reached
+    let late_voter = Address::random(&env);
+    set_ledger(&env, 1050, 3);
+    assert_eq!(
+        dao_client.try_v_abstain(&late_voter, &prop_id),
+        Err(Ok(ContractError::PropDeadlinePassed.into()))
+    );
+
+    dao_client.execute(&prop_id);
+    assert_eq!(100, token_client.balance(&voter));
+}
+
+#[test]
+fn test_proposal_duration_bounds() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    set_ledger(&env, 1000, 1);
+    assert_eq!(100, dao_client.max_dur());
+
+    let too_short = mint_prop(&env, &token_id, &dao_id, &voter, 1009);
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &too_short),
+        Err(Ok(ContractError::MinDurationNotSatisfied.into()))
+    );
+
+    let in_the_past = mint_prop(&env, &token_id, &dao_id, &voter, 900);
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &in_the_past),
+        Err(Ok(ContractError::MinDurationNotSatisfied.into()))
+    );
+
+    let too_long = mint_prop(&env, &token_id, &dao_id, &voter, 1101);
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &too_long),
+        Err(Ok(ContractError::MaxDurationExceeded.into()))
+    );
+
+    let longest = mint_prop(&env, &token_id, &dao_id, &voter, 1100);
+    assert_eq!(0, dao_client.c_prop(&voter, &longest));
+
+    // settings can't make the min duration larger than the max
+    for (fun_name, duration) in [(symbol!("set_mindur"), 101u32), (symbol!("set_maxdur"), 9u32)] {
+        let instr = vec![&env, setting_instr(&dao_id, fun_name, vec![&env, duration.into_val(&env)])];
+        let (now, seq) = (env.ledger().timestamp(), env.ledger().sequence());
+        let prop_id = dao_client.c_prop(&voter, &Proposal { end_time: now + 50, instr });
+        set_ledger(&env, now + 10, seq + 1);
+        dao_client.vote_for(&voter, &prop_id);
+        set_ledger(&env, now + 50, seq + 2);
+        assert_eq!(
+            dao_client.try_execute(&prop_id),
+            Err(Ok(ContractError::InvalidDurations.into()))
+        );
+    }
+    assert_eq!((10, 100), (dao_client.min_dur(), dao_client.max_dur()));
+}
+
+#[test]
+fn test_function_thresholds() {
+    let env: Env = Default::default();
+    let voter_1 = Address::random(&env);
+    let voter_2 = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter_1, 60), (&voter_2, 40)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    assert_eq!(
+        Threshold { approval: 50, quorum: 0 },
+        dao_client.threshold(&token_id, &symbol!("mint"))
+    );
+
+    // require 66% approval and 50% quorum for minting
+    set_ledger(&env, 1000, 1);
+    let prop = Proposal {
+        end_time: 1050,
+        instr: vec![
+            &env,
+            ProposalInstr {
+                c_id: dao_id.clone(),
+                fun_name: symbol!("set_thresh"),
+                args: vec![
+                    &env,
+                    token_id.into_val(&env),
+                    symbol!("mint").into_val(&env),
+                    66u32.into_val(&env),
+                    50u32.into_val(&env),
+                ],
+            },
+        ],
+    };
+    let prop_id = dao_client.c_prop(&voter_1, &prop);
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&voter_1, &prop_id);
+    set_ledger(&env, 1050, 3);
+    dao_client.execute(&prop_id);
+
+    assert_eq!(
+        Threshold { approval: 66, quorum: 50 },
+        dao_client.threshold(&token_id, &symbol!("mint"))
+    );
+
+    // 60% approval is a majority, but not enough to mint
+    let prop = mint_prop(&env, &token_id, &dao_id, &voter_1, 1100);
+    let prop_id = dao_client.c_prop(&voter_1, &prop);
+    set_ledger(&env, 1060, 4);
+    dao_client.vote_for(&voter_1, &prop_id);
+    dao_client.v_against(&voter_2, &prop_id);
+    set_ledger(&env, 1100, 5);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::NotEnoughApproval.into()))
+    );
+
+    // unanimous, but only 40% of the supply voted
+    let prop = mint_prop(&env, &token_id, &dao_id, &voter_2, 1150);
+    let prop_id = dao_client.c_prop(&voter_2, &prop);
+    set_ledger(&env, 1110, 6);
+    dao_client.vote_for(&voter_2, &prop_id);
+    set_ledger(&env, 1150, 7);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::QuorumNotReached.into()))
+    );
+
+    // the mint threshold doesn't apply to proposals that don't mint
+    env.budget().reset();
+    let prop = Proposal {
+        end_time: 1200,
+        instr: vec![
+            &env,
+            ProposalInstr {
+                c_id: dao_id.clone(),
+                fun_name: symbol!("rm_thresh"),
+                args: vec![&env, token_id.into_val(&env), symbol!("mint").into_val(&env)],
+            },
+        ],
+    };
+    let prop_id = dao_client.c_prop(&voter_1, &prop);
+    set_ledger(&env, 1160, 8);
+    dao_client.vote_for(&voter_1, &prop_id);
+    dao_client.v_against(&voter_2, &prop_id);
+    set_ledger(&env, 1200, 9);
+    dao_client.execute(&prop_id);
+
+    assert_eq!(
+        Threshold { approval: 50, quorum: 0 },
+        dao_client.threshold(&token_id, &symbol!("mint"))
+    );
+}
+
+#[test]
+fn test_allowlist() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+
+    assert!(!dao_client.al_mode());
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![
+            &env,
+            setting_instr(&dao_id, symbol!("set_almode"), vec![&env, true.into_val(&env)]),
+        ],
+    );
+    assert!(dao_client.al_mode());
+    assert!(!dao_client.allowed(&token_id, &symbol!("mint")));
+
+    // the mint is the second instruction
+    let mut prop = mint_prop(&env, &token_id, &dao_id, &voter, env.ledger().timestamp() + 50);
+    prop.instr.push_front(setting_instr(
+        &dao_id,
+        symbol!("set_quorum"),
+        vec![&env, 10u32.into_val(&env)],
+    ));
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &prop),
+        Err(Ok(Status::from_contract_error(
+            ContractError::InstrNotAllowed as u32 + 1
+        )))
+    );
+
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![
+            &env,
+            setting_instr(
+                &dao_id,
+                symbol!("allow_fn"),
+                vec![&env, token_id.into_val(&env), symbol!("mint").into_val(&env)],
+            ),
+        ],
+    );
+    assert!(dao_client.allowed(&token_id, &symbol!("mint")));
+
+    let prop = mint_prop(&env, &token_id, &dao_id, &voter, env.ledger().timestamp() + 50);
+    dao_client.c_prop(&voter, &prop);
+}
+
+#[test]
+fn test_proposal_expiry() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    set_ledger(&env, 1000, 1);
+    let prop = mint_prop(&env, &token_id, &dao_id, &voter, 1050);
+    let prop_id = dao_client.c_prop(&voter, &prop);
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&voter, &prop_id);
+
+    assert_eq!(100, dao_client.grace());
+    assert_eq!(1150, dao_client.expiry(&prop_id));
+
+    set_ledger(&env, 1150, 3);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::ProposalExpired.into()))
+    );
+    assert_eq!(0, token_client.balance(&voter));
+
+    // a longer grace period only applies to new proposals
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![&env, setting_instr(&dao_id, symbol!("set_grace"), vec![&env, 1000u32.into_val(&env)])],
+    );
+    assert_eq!((1000, 1150), (dao_client.grace(), dao_client.expiry(&prop_id)));
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::ProposalExpired.into()))
+    );
+}
+
+#[test]
+fn test_proposal_listing() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    set_ledger(&env, 1000, 1);
+    assert_eq!(0, dao_client.prop_count());
+    assert_eq!(0, dao_client.proposals(&0, &10).len());
+
+    for end_time in [1050, 1060, 1100] {
+        dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, end_time));
+    }
+    assert_eq!(3, dao_client.prop_count());
+    assert_eq!(vec![&env, 0, 1, 2], dao_client.active());
+
+    let page = dao_client.proposals(&1, &10);
+    assert_eq!(2, page.len());
+    assert_eq!(1060, page.get_unchecked(0).unwrap().proposal.end_time);
+    assert_eq!(1100, page.get_unchecked(1).unwrap().proposal.end_time);
+    assert_eq!(1, dao_client.proposals(&0, &1).len());
+    assert_eq!(0, dao_client.proposals(&3, &10).len());
+
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&voter, &0);
+    set_ledger(&env, 1050, 3);
+    dao_client.execute(&0);
+    assert_eq!(vec![&env, 1, 2], dao_client.active());
+
+    // proposal 1 expires after its grace period
+    set_ledger(&env, 1160, 4);
+    assert_eq!(vec![&env, 2], dao_client.active());
+
+    // and is pruned from the index when the next proposal is created
+    dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1250));
+    assert_eq!(vec![&env, 2, 3], dao_client.active());
+}
+
+#[test]
+fn test_init_requires_token_admin() {
+    let env: Env = Default::default();
+    let token_contract_id = env.register_contract_wasm(None, tokenclient::WASM);
+    let dao_contract_id = env.register_contract(None, DaoContract);
+    let admin = Address::random(&env);
+
+    let token_client = tokenclient::Client::new(&env, &token_contract_id);
+    token_client.initialize(
+        &admin,
+        &7,
+        &Bytes::from_array(&env, b"DAO TOKEN"),
+        &Bytes::from_array(&env, b"DTOKEN"),
+    );
+
+    let dao_client = DaoContractClient::new(&env, &dao_contract_id);
+    assert_eq!(
+        dao_client.try_init(&token_contract_id, &TimeModel::Timestamp, &10, &100, &100, &0, &10),
+        Err(Ok(ContractError::NotTokenAdmin.into()))
+    );
+
+    token_client.set_admin(&admin, &Address::from_contract_id(&env, &dao_contract_id));
+    // no proposal could ever be created
+    assert_eq!(
+        dao_client.try_init(&token_contract_id, &TimeModel::Timestamp, &101, &100, &100, &0, &10),
+        Err(Ok(ContractError::InvalidDurations.into()))
+    );
+    dao_client.init(&token_contract_id, &TimeModel::Timestamp, &10, &100, &100, &0, &10);
+}
+
+fn xfer_instr(
+    env: &Env,
+    token_id: &BytesN<32>,
+    dao_id: &BytesN<32>,
+    to: &Address,
+    amount: i128,
+) -> ProposalInstr {
+    ProposalInstr {
+        c_id: token_id.clone(),
+        fun_name: symbol!("xfer"),
+        args: vec![
+            env,
+            Address::from_contract_id(env, dao_id).into_val(env),
+            to.into_val(env),
+            amount.into_val(env),
+        ],
+    }
+}
+
+#[test]
+fn test_committee() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let member_1 = Address::random(&env);
+    let member_2 = Address::random(&env);
+    let grantee = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+
+    // fund the treasury and create a grants committee
+    let committee = Committee {
+        members: vec![&env, member_1.clone(), member_2.clone()],
+        threshold: Threshold { approval: 50, quorum: 50 },
+        budget: 30,
+        period: 1000,
+        fns: vec![&env],
+    };
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![
+            &env,
+            mint_prop(&env, &token_id, &dao_id, &Address::from_contract_id(&env, &dao_id), 0)
+                .instr
+                .get_unchecked(0)
+                .unwrap(),
+            setting_instr(&dao_id, symbol!("add_comm"), vec![&env, committee.into_val(&env)]),
+        ],
+    );
+    assert_eq!(2, dao_client.committee(&0).members.len());
+
+    // only members can propose and vote, and every member has one vote
+    let grant = Proposal {
+        end_time: 1100,
+        instr: vec![&env, xfer_instr(&env, &token_id, &dao_id, &grantee, 20)],
+    };
+    assert_eq!(
+        dao_client.try_c_cprop(&voter, &0, &grant),
+        Err(Ok(ContractError::NotCommitteeMember.into()))
+    );
+    let prop_id = dao_client.c_cprop(&member_1, &0, &grant);
+    assert_eq!(PropOrigin::Committee(0), dao_client.proposal(&prop_id).origin);
+
+    set_ledger(&env, 1060, 4);
+    assert_eq!(
+        dao_client.try_vote_for(&voter, &prop_id),
+        Err(Ok(ContractError::NotCommitteeMember.into()))
+    );
+    dao_client.vote_for(&member_1, &prop_id);
+    assert_eq!(1, dao_client.votes(&prop_id).v_for);
+
+    // a second grant in the same period would exceed the budget
+    let second_grant = dao_client.c_cprop(&member_2, &0, &grant);
+    set_ledger(&env, 1070, 5);
+    dao_client.vote_for(&member_2, &second_grant);
+
+    set_ledger(&env, 1100, 6);
+    dao_client.execute(&prop_id);
+    assert_eq!(20, token_client.balance(&grantee));
+    assert_eq!(80, token_client.balance(&Address::from_contract_id(&env, &dao_id)));
+    assert_eq!(
+        dao_client.try_execute(&second_grant),
+        Err(Ok(ContractError::BudgetExceeded.into()))
+    );
+
+    // committees can only spend from the treasury or call the functions they were given
+    let mint = mint_prop(&env, &token_id, &dao_id, &member_1, 1200);
+    assert_eq!(
+        dao_client.try_c_cprop(&member_1, &0, &mint),
+        Err(Ok(ContractError::CommitteeInstrNotAllowed.into()))
+    );
+
+    // the dao can revoke the committee
+    let grant = Proposal { end_time: 1200, ..grant };
+    let prop_id = dao_client.c_cprop(&member_1, &0, &grant);
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![&env, setting_instr(&dao_id, symbol!("rm_comm"), vec![&env, 0u32.into_val(&env)])],
+    );
+    assert_eq!(
+        dao_client.try_vote_for(&member_1, &prop_id),
+        Err(Ok(ContractError::CommitteeRevoked.into()))
+    );
+}
+
+#[test]
+fn test_pause() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let guardian = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+
+    let guardian_setting = Guardian { address: guardian.clone(), duration: 100 };
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![&env, setting_instr(&dao_id, symbol!("set_guard"), vec![&env, guardian_setting.into_val(&env)])],
+    );
+
+    let prop_id = dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1100));
+    set_ledger(&env, 1060, 4);
+    dao_client.vote_for(&voter, &prop_id);
+
+    assert_eq!(
+        dao_client.try_pause(&voter),
+        Err(Ok(ContractError::NotGuardian.into()))
+    );
+    dao_client.pause(&guardian);
+    assert_eq!(1160, dao_client.pause_end());
+    let (event_id, topics, data) = env.events().all().last().unwrap().unwrap();
+    assert_eq!(dao_id, event_id);
+    assert_eq!(topics, (symbol!("pause"), guardian.clone()).into_val(&env));
+    assert_eq!(1160, u64::try_from_val(&env, &data).unwrap());
+
+    // voting continues, but nothing can be created or executed
+    set_ledger(&env, 1100, 5);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::Paused.into()))
+    );
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1150)),
+        Err(Ok(ContractError::Paused.into()))
+    );
+
+    // except for unpausing
+    pass_proposal(
+        &env,
+        &dao_client,
+        &voter,
+        vec![&env, setting_instr(&dao_id, symbol!("unpause"), vec![&env])],
+    );
+    assert_eq!(1150, dao_client.pause_end());
+    let (event_id, topics, data) = env.events().all().last().unwrap().unwrap();
+    assert_eq!(dao_id, event_id);
+    assert_eq!(topics, (symbol!("unpause"),).into_val(&env));
+    assert_eq!(1150, u64::try_from_val(&env, &data).unwrap());
+    dao_client.execute(&prop_id);
+    assert_eq!(100, token_client.balance(&voter));
+
+    // the guardian has to wait before pausing again, and pauses expire on their own
+    assert_eq!(
+        dao_client.try_pause(&guardian),
+        Err(Ok(ContractError::PauseCooldown.into()))
+    );
+    set_ledger(&env, 1250, 8);
+    dao_client.pause(&guardian);
+    set_ledger(&env, 1350, 9);
+    dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1400));
+}
+
+#[test]
+fn test_early_execution() {
+    let env: Env = Default::default();
+    let big = Address::random(&env);
+    let small = Address::random(&env);
+    let other = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&big, 60), (&small, 30), (&other, 10)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+
+    // a majority of the supply voted for, but early execution is off by default
+    let prop_id = dao_client.c_prop(
+        &big,
+        &Proposal {
+            end_time: 1100,
+            instr: vec![
+                &env,
+                setting_instr(&dao_id, symbol!("set_quorum"), vec![&env, 10u32.into_val(&env)]),
+            ],
+        },
+    );
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&big, &prop_id);
+    assert!(!dao_client.early());
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::TooEarlyToExecute.into()))
+    );
+
+    pass_proposal(
+        &env,
+        &dao_client,
+        &big,
+        vec![&env, setting_instr(&dao_id, symbol!("set_early"), vec![&env, true.into_val(&env)])],
+    );
+    assert!(dao_client.early());
+    dao_client.execute(&prop_id);
+    assert_eq!(10, dao_client.quorum());
+
+    // the votes that are left could still flip the result
+    env.budget().reset();
+    let prop_id = dao_client.c_prop(&big, &mint_prop(&env, &token_id, &dao_id, &other, 1150));
+    set_ledger(&env, 1070, 5);
+    dao_client.vote_for(&small, &prop_id);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::TooEarlyToExecute.into()))
+    );
+
+    dao_client.vote_for(&big, &prop_id);
+    let balance = token_client.balance(&other);
+    dao_client.execute(&prop_id);
+    assert_eq!(balance + 100, token_client.balance(&other));
+}
+
+#[test]
+fn test_early_execution_delegation() {
+    let env: Env = Default::default();
+    let holder = Address::random(&env);
+    let top = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&holder, 60), (&top, 40)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+    pass_proposal(
+        &env,
+        &dao_client,
+        &holder,
+        vec![&env, setting_instr(&dao_id, symbol!("set_early"), vec![&env, true.into_val(&env)])],
+    );
+    token_client.live_dlg(&holder, &top);
+
+    // the holder didn't vote, so it can still take its power back from the top
+    let prop_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1150));
+    set_ledger(&env, 1060, 4);
+    dao_client.vote_for(&top, &prop_id);
+    assert_eq!(100, dao_client.votes(&prop_id).v_for);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::TooEarlyToExecute.into()))
+    );
+
+    env.budget().reset();
+    dao_client.v_against(&holder, &prop_id);
+    let votes = dao_client.votes(&prop_id);
+    assert_eq!((40, 60), (votes.v_for, votes.v_against));
+
+    // once it voted the same way, nothing can be taken back anymore
+    let prop_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1150));
+    set_ledger(&env, 1070, 5);
+    dao_client.vote_for(&top, &prop_id);
+    env.budget().reset();
+    dao_client.vote_for(&holder, &prop_id);
+    dao_client.execute(&prop_id);
+}
+
+#[test]
+fn test_ledger_time_model() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) =
+        setup_dao_with_model(&env, &[(&voter, 100)], TimeModel::Ledger);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    assert_eq!(TimeModel::Ledger, dao_client.time_model());
+    set_ledger(&env, 1000, 1);
+
+    // durations are counted in ledgers, so timestamps don't matter
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 5)),
+        Err(Ok(ContractError::MinDurationNotSatisfied.into()))
+    );
+    assert_eq!(
+        dao_client.try_c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 200)),
+        Err(Ok(ContractError::MaxDurationExceeded.into()))
+    );
+    let prop_id = dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 50));
+    assert_eq!(150, dao_client.expiry(&prop_id));
+
+    set_ledger(&env, 5000, 2);
+    dao_client.vote_for(&voter, &prop_id);
+
+    set_ledger(&env, 1_000_000, 20);
+    assert_eq!(
+        dao_client.try_execute(&prop_id),
+        Err(Ok(ContractError::TooEarlyToExecute.into()))
+    );
+
+    set_ledger(&env, 1_000_100, 50);
+    assert_eq!(
+        dao_client.try_vote_for(&Address::random(&env), &prop_id),
+        Err(Ok(ContractError::PropDeadlinePassed.into()))
+    );
+    let balance = token_client.balance(&voter);
+    dao_client.execute(&prop_id);
+    assert_eq!(balance + 100, token_client.balance(&voter));
+}
+
+#[test]
+fn test_tally_migration() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+
+    // proposals that were voted on before the votes were stored in one tally
+    let prop_id = dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1100));
+    let cleaned_id = dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1050));
+    let has_votes = |prop_id: u32| {
+        env.as_contract(&dao_id, || {
+            [
+                DataKey::Tally(prop_id),
+                DataKey::ForVotes(prop_id),
+                DataKey::AgainstV(prop_id),
+                DataKey::AbstainV(prop_id),
+            ]
+            .map(|key| env.storage().has(&key))
+        })
+    };
+    env.as_contract(&dao_id, || {
+        for prop_id in [prop_id, cleaned_id] {
+            env.storage().remove(&DataKey::Tally(prop_id));
+            env.storage().set(&DataKey::ForVotes(prop_id), &60i128);
+            env.storage().set(&DataKey::AgainstV(prop_id), &30i128);
+            env.storage().set(&DataKey::AbstainV(prop_id), &10i128);
+        }
+    });
+    let votes = dao_client.votes(&prop_id);
+    assert_eq!((60, 30, 10, 0), (votes.v_for, votes.v_against, votes.v_abstain, votes.voters));
+
+    // the next vote is added to the old votes, which are moved to the tally
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&voter, &prop_id);
+    let votes = dao_client.votes(&prop_id);
+    assert_eq!((160, 30, 10, 1), (votes.v_for, votes.v_against, votes.v_abstain, votes.voters));
+    assert_eq!([true, false, false, false], has_votes(prop_id));
+
+    // cleaning up keeps the old votes in the summary, and removes them
+    set_ledger(&env, 1150, 3);
+    dao_client.cleanup(&cleaned_id, &vec![&env], &voter);
+    let summary = dao_client.summary(&cleaned_id);
+    assert_eq!((60, 30, 10), (summary.votes.v_for, summary.votes.v_against, summary.votes.v_abstain));
+    assert_eq!([false; 4], has_votes(cleaned_id));
+}
+
+// vote for, against and abstain and read the votes back, as they were stored before the tally
+fn legacy_votes(env: &Env, prop_id: u32) -> (i128, i128, i128) {
+    let keys = [
+        DataKey::ForVotes(prop_id),
+        DataKey::AgainstV(prop_id),
+        DataKey::AbstainV(prop_id),
+    ];
+    let get = |key: &DataKey| -> i128 { env.storage().get(key).unwrap_or(Ok(0)).unwrap() };
+    for key in keys.iter() {
+        env.storage().set(key, &(get(key) + 10));
+    }
+    (get(&keys[0]), get(&keys[1]), get(&keys[2]))
+}
+
+#[test]
+fn test_tally_budget() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (_, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+    let dao_id = dao_client.contract_id.clone();
+
+    let mut budget = env.budget();
+    budget.reset();
+    env.as_contract(&dao_id, || legacy_votes(&env, 0));
+    let legacy = (budget.cpu_instruction_cost(), budget.memory_bytes_cost());
+
+    // the tally is stored when the proposal is created
+    env.as_contract(&dao_id, || {
+        let votes = crate::proposal::votes_counts(&env, 1);
+        env.storage().set(&DataKey::Tally(1), &votes)
+    });
+    budget.reset();
+    env.as_contract(&dao_id, || {
+        crate::proposal::add_for_votes(&env, 1, 10);
+        crate::proposal::add_against_votes(&env, 1, 10);
+        crate::proposal::add_abstain_votes(&env, 1, 10);
+        crate::proposal::votes_counts(&env, 1)
+    });
+    let tally = (budget.cpu_instruction_cost(), budget.memory_bytes_cost());
+
+    // bytes of the ledger entries: every entry stores at least the contract id, key and value
+    let entry_bytes = |key: DataKey, value: RawVal| -> u32 {
+        32 + key.serialize(&env).len() + value.serialize(&env).len()
+    };
+    let votes = env.as_contract(&dao_id, || crate::proposal::votes_counts(&env, 1));
+    let legacy_bytes: u32 = [DataKey::ForVotes(0), DataKey::AgainstV(0), DataKey::AbstainV(0)]
+        .into_iter()
+        .map(|key| entry_bytes(key, 10i128.into_val(&env)))
+        .sum();
+    let tally_bytes = entry_bytes(DataKey::Tally(1), votes.into_val(&env));
+
+    std::println!(
+        "cpu: {} -> {}, mem: {} -> {}, storage entries: 3 -> 1, entry bytes: {} -> {}",
+        legacy.0, tally.0, legacy.1, tally.1, legacy_bytes, tally_bytes
+    );
+    assert!(tally.0 < legacy.0);
+    assert!(tally_bytes < legacy_bytes);
+}
+
+#[test]
+fn test_cleanup() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let voter_2 = Address::random(&env);
+    let cleaner = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100), (&voter_2, 50)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+    set_ledger(&env, 1000, 1);
+
+    // fund the treasury and set aside 10 tokens for bounties of 5
+    let fund = vec![
+        &env,
+        mint_prop(&env, &token_id, &dao_id, &Address::from_contract_id(&env, &dao_id), 0)
+            .instr
+            .get_unchecked(0)
+            .unwrap(),
+        setting_instr(
+            &dao_id,
+            symbol!("set_bounty"),
+            vec![&env, 5i128.into_val(&env), 10i128.into_val(&env)],
+        ),
+    ];
+    pass_proposal(&env, &dao_client, &voter, fund.clone());
+    assert_eq!(Bounty { amount: 5, fund: 10 }, dao_client.bounty());
+
+    let executed_id = 0;
+    dao_client.cleanup(&executed_id, &vec![&env, voter.clone()], &cleaner);
+    assert_eq!(5, token_client.balance(&cleaner));
+
+    let summary = dao_client.summary(&executed_id);
+    assert_eq!(PropOutcome::Executed, summary.outcome);
+    assert_eq!((100, 1), (summary.votes.v_for, summary.votes.voters));
+    assert_eq!(env.crypto().sha256(&fund.serialize(&env)), summary.instr_hash);
+    assert_eq!(100, dao_client.votes(&executed_id).v_for);
+    assert_eq!(
+        dao_client.try_proposal(&executed_id).err(),
+        Some(Ok(ContractError::InvalidProposalId.into()))
+    );
+    env.as_contract(&dao_id, || {
+        assert!(!env.storage().has(&DataKey::Proposal(executed_id)));
+        assert!(!env.storage().has(&DataKey::PropStart(executed_id)));
+        assert!(!env.storage().has(&DataKey::PropExpiry(executed_id)));
+        assert!(!env.storage().has(&DataKey::PropComm(executed_id)));
+        assert!(!env.storage().has(&DataKey::Voted(ProposalVoted {
+            voter: voter.clone(),
+            prop_id: executed_id
+        })));
+    });
+
+    // the bounty is only paid for voters that had something to remove
+    dao_client.cleanup(&executed_id, &vec![&env], &cleaner);
+    dao_client.cleanup(&executed_id, &vec![&env, voter.clone(), voter_2.clone()], &cleaner);
+    assert_eq!(5, token_client.balance(&cleaner));
+    assert_eq!(
+        dao_client.try_vote_for(&voter_2, &executed_id),
+        Err(Ok(ContractError::InvalidProposalId.into()))
+    );
+
+    // proposals can only be cleaned up once they can't be voted on or executed anymore
+    let failed_id = dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1150));
+    set_ledger(&env, 1060, 4);
+    dao_client.v_against(&voter_2, &failed_id);
+    set_ledger(&env, 1150, 5);
+    assert_eq!(
+        dao_client.try_cleanup(&failed_id, &vec![&env], &cleaner),
+        Err(Ok(ContractError::NotFinalized.into()))
+    );
+    assert_eq!(1, dao_client.proposals(&0, &10).len());
+
+    set_ledger(&env, 1250, 6);
+    dao_client.cleanup(&failed_id, &vec![&env, voter_2.clone()], &cleaner);
+    assert_eq!(PropOutcome::Defeated, dao_client.summary(&failed_id).outcome);
+    assert_eq!(50, dao_client.votes(&failed_id).v_against);
+    assert_eq!(10, token_client.balance(&cleaner));
+    assert_eq!(Bounty { amount: 5, fund: 0 }, dao_client.bounty());
+    assert_eq!(0, dao_client.proposals(&0, &10).len());
+
+    // a bounty that the treasury can't cover is skipped instead of blocking the cleanup
+    env.budget().reset();
+    let expired_id = dao_client.c_prop(&voter, &mint_prop(&env, &token_id, &dao_id, &voter, 1300));
+    set_ledger(&env, 1260, 7);
+    dao_client.vote_for(&voter, &expired_id);
+    env.as_contract(&dao_id, || {
+        env.storage()
+            .set(&DataKey::Bounty, &Bounty { amount: 200, fund: 1000 })
+    });
+    set_ledger(&env, 1400, 8);
+    dao_client.cleanup(&expired_id, &vec![&env, voter.clone()], &cleaner);
+    assert_eq!(PropOutcome::Expired, dao_client.summary(&expired_id).outcome);
+    assert_eq!(10, token_client.balance(&cleaner));
+    assert_eq!(Bounty { amount: 200, fund: 1000 }, dao_client.bounty());
+}
+
+#[test]
+fn test_not_initialized() {
+    let env: Env = Default::default();
+    let user = Address::random(&env);
+    let dao_id = env.register_contract(None, DaoContract);
+    let dao_client = DaoContractClient::new(&env, &dao_id);
+    let proposal = Proposal { end_time: 100, instr: vec![&env] };
+    let not_init: Status = ContractError::NotInitialized.into();
+
+    assert_eq!(dao_client.try_c_prop(&user, &proposal), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_c_cprop(&user, &0, &proposal), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_execute(&0), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_proposal(&0).err(), Some(Ok(not_init)));
+    assert_eq!(dao_client.try_expiry(&0), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_prop_count(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_proposals(&0, &10).err(), Some(Ok(not_init)));
+    assert_eq!(dao_client.try_active(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_vote_for(&user, &0), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_v_against(&user, &0), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_v_abstain(&user, &0), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_votes(&0).err(), Some(Ok(not_init)));
+    assert_eq!(dao_client.try_cleanup(&0, &vec![&env], &user), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_summary(&0).err(), Some(Ok(not_init)));
+    assert_eq!(dao_client.try_bounty(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_time_model(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_min_dur(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_max_dur(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_grace(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_quorum(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_early(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_min_prop_p(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_threshold(&dao_id, &symbol!("mint")), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_al_mode(), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_allowed(&dao_id, &symbol!("mint")), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_committee(&0).err(), Some(Ok(not_init)));
+    assert_eq!(dao_client.try_pause(&user), Err(Ok(not_init)));
+    assert_eq!(dao_client.try_pause_end(), Err(Ok(not_init)));
+
+    // the address is needed to make the dao the token admin before init
+    assert_eq!(Address::from_contract_id(&env, &dao_id), dao_client.address());
+}
+
+#[test]
+fn test_init_once() {
+    let env: Env = Default::default();
+    let voter = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&voter, 100)]);
+
+    assert_eq!(
+        dao_client.try_init(
+            &token_client.contract_id,
+            &TimeModel::Timestamp,
+            &10,
+            &100,
+            &100,
+            &0,
+            &10
+        ),
+        Err(Ok(ContractError::AlreadyInitialized.into()))
+    );
+}
+
+#[test]
+fn test_delegation_chain() {
+    let env: Env = Default::default();
+    let holder = Address::random(&env);
+    let delegate = Address::random(&env);
+    let top = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&holder, 30), (&delegate, 20), (&top, 10)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    // holder -> delegate -> top
+    set_ledger(&env, 1000, 1);
+    token_client.live_dlg(&holder, &delegate);
+    token_client.live_dlg(&delegate, &top);
+    assert_eq!((50, 60), (token_client.power(&delegate), token_client.power(&top)));
+
+    let prop_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1050));
+    let later_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1050));
+
+    // the top votes with the whole chain
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&top, &prop_id);
+    assert_eq!(60, dao_client.votes(&prop_id).v_for);
+
+    // delegating again doesn't change the chain the proposal was created with
+    token_client.r_live_dlg(&holder);
+
+    // the cpu budget is shared by all the calls of the test
+    env.budget().reset();
+
+    // voting directly takes the power back from the delegates above
+    dao_client.v_against(&holder, &prop_id);
+    dao_client.v_abstain(&delegate, &prop_id);
+    let votes = dao_client.votes(&prop_id);
+    assert_eq!(
+        (10, 30, 20, 3),
+        (votes.v_for, votes.v_against, votes.v_abstain, votes.voters)
+    );
+    assert_eq!(Some(Ballot { side: VoteSide::For, weight: 10 }), dao_client.ballot(&prop_id, &top));
+    assert_eq!(
+        Some(Ballot { side: VoteSide::Abstain, weight: 20 }),
+        dao_client.ballot(&prop_id, &delegate)
+    );
+
+    // and delegates voting later don't count what was voted below them
+    dao_client.vote_for(&holder, &later_id);
+    dao_client.v_against(&top, &later_id);
+    let votes = dao_client.votes(&later_id);
+    assert_eq!((30, 30, 0), (votes.v_for, votes.v_against, votes.v_abstain));
+    assert_eq!(None, dao_client.ballot(&later_id, &delegate));
+}
+
+#[test]
+fn test_delegate_balance() {
+    let env: Env = Default::default();
+    let holder = Address::random(&env);
+    let delegate = Address::random(&env);
+    let top = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&holder, 30), (&delegate, 20), (&top, 10)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    // the delegate keeps its tokens in its balance, which counts for it through its own delegate
+    set_ledger(&env, 1000, 1);
+    token_client.r_delegate(&delegate, &delegate, &20);
+    token_client.live_dlg(&holder, &delegate);
+    token_client.live_dlg(&delegate, &top);
+    assert_eq!((50, 60), (token_client.power(&delegate), token_client.power(&top)));
+    let prop_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1050));
+
+    // voting directly takes its balance back from the top too
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&top, &prop_id);
+    dao_client.v_against(&delegate, &prop_id);
+    let votes = dao_client.votes(&prop_id);
+    assert_eq!((10, 50), (votes.v_for, votes.v_against));
+}
+
+#[test]
+fn test_timed_delegation() {
+    let env: Env = Default::default();
+    let holder = Address::random(&env);
+    let delegate = Address::random(&env);
+    let top = Address::random(&env);
+    let (token_client, dao_client) = setup_dao(&env, &[(&holder, 30), (&delegate, 20), (&top, 10)]);
+    let (token_id, dao_id) = (token_client.contract_id.clone(), dao_client.contract_id.clone());
+
+    set_ledger(&env, 1000, 1);
+    token_client.r_delegate(&holder, &holder, &30);
+    token_client.dlg_until(&holder, &delegate, &30, &3);
+    token_client.live_dlg(&delegate, &top);
+    assert_eq!((50, 30), (token_client.power(&delegate), token_client.power(&top)));
+    let prop_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1050));
+
+    // only the live delegated part of the delegate's vote is taken from the top
+    set_ledger(&env, 1010, 2);
+    dao_client.vote_for(&top, &prop_id);
+    dao_client.v_against(&delegate, &prop_id);
+    let votes = dao_client.votes(&prop_id);
+    assert_eq!((10, 50), (votes.v_for, votes.v_against));
+
+    // proposals created after the expiry don't count it
+    env.budget().reset();
+    set_ledger(&env, 1020, 3);
+    let later_id = dao_client.c_prop(&top, &mint_prop(&env, &token_id, &dao_id, &top, 1070));
+    set_ledger(&env, 1030, 4);
+    dao_client.v_against(&delegate, &later_id);
+    assert_eq!(20, dao_client.votes(&later_id).v_against);
+}
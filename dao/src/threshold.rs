@@ -0,0 +1,112 @@
+use soroban_sdk::{contracttype, panic_with_error, unwrap::UnwrapOptimized, BytesN, Env, Symbol};
+
+use crate::{
+    ballot::Movable,
+    data_keys::DataKey,
+    errors::ContractError,
+    proposal::{Proposal, VotesCount},
+    settings::get_quorum,
+};
+
+// a function of a contract that a proposal instruction can call
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct FunKey {
+    pub c_id: BytesN<32>,
+    pub fun_name: Symbol,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Threshold {
+    // percentage of for votes out of for + against votes needed to pass.
+    // for votes also always need to be more than against votes.
+    pub approval: u32,
+    // percentage of the token supply that has to vote for or abstain
+    pub quorum: u32,
+}
+
+pub fn default_threshold(env: &Env) -> Threshold {
+    Threshold {
+        approval: 50,
+        quorum: get_quorum(env),
+    }
+}
+
+pub fn set_threshold(env: &Env, key: FunKey, threshold: Threshold) {
+    if threshold.approval > 100 || threshold.quorum > 100 {
+        panic_with_error!(env, ContractError::InvalidThreshold)
+    }
+    env.storage().set(&DataKey::Threshold(key), &threshold)
+}
+
+pub fn remove_threshold(env: &Env, key: FunKey) {
+    env.storage().remove(&DataKey::Threshold(key))
+}
+
+// threshold for calling `key`, falls back to the dao wide settings
+pub fn get_threshold(env: &Env, key: FunKey) -> Threshold {
+    env.storage()
+        .get(&DataKey::Threshold(key))
+        .unwrap_or_else(|| Ok(default_threshold(env)))
+        .unwrap_optimized()
+}
+
+// the strictest threshold of all the instructions in the proposal
+pub fn proposal_threshold(env: &Env, proposal: &Proposal) -> Threshold {
+    let mut strictest = default_threshold(env);
+
+    for instr in proposal.instr.iter() {
+        let instr = instr.unwrap_optimized();
+        let threshold = get_threshold(
+            env,
+            FunKey {
+                c_id: instr.c_id,
+                fun_name: instr.fun_name,
+            },
+        );
+
+        strictest.approval = strictest.approval.max(threshold.approval);
+        strictest.quorum = strictest.quorum.max(threshold.quorum);
+    }
+
+    strictest
+}
+
+// whether the proposal passes no matter how the votes that are left are cast.
+// Votes can't be changed, but live delegators that didn't vote can still take their power
+// back from a delegate. So the worst case is that all of the remaining supply and the
+// movable votes vote against.
+pub fn is_decided(
+    threshold: &Threshold,
+    votes: &VotesCount,
+    movable: &Movable,
+    supply: i128,
+) -> bool {
+    let remaining = (supply - votes.v_for - votes.v_against - votes.v_abstain).max(0);
+    let v_for = votes.v_for - movable.v_for;
+    let abstain = votes.v_abstain - movable.v_abstain;
+    let against = votes.v_against + remaining + movable.v_for + movable.v_abstain;
+
+    v_for > against
+        && v_for * 100 >= (threshold.approval as i128) * (v_for + against)
+        && (v_for + abstain) * 100 >= (threshold.quorum as i128) * supply
+}
+
+// whether the votes were enough for the proposal to be executed
+pub fn passes(threshold: &Threshold, votes: &VotesCount, supply: i128) -> bool {
+    votes.v_for > votes.v_against
+        && votes.v_for * 100 >= (threshold.approval as i128) * (votes.v_for + votes.v_against)
+        && (votes.v_for + votes.v_abstain) * 100 >= (threshold.quorum as i128) * supply
+}
+
+// `supply` -> token supply when the proposal was created
+pub fn check_threshold(env: &Env, threshold: &Threshold, votes: &VotesCount, supply: i128) {
+    if votes.v_for * 100 < (threshold.approval as i128) * (votes.v_for + votes.v_against) {
+        panic_with_error!(env, ContractError::NotEnoughApproval)
+    }
+
+    if (votes.v_for + votes.v_abstain) * 100 < (threshold.quorum as i128) * supply {
+        panic_with_error!(env, ContractError::QuorumNotReached)
+    }
+}
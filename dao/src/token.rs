@@ -6,14 +6,15 @@ pub mod tokenclient {
     soroban_sdk::contractimport!(file = "../target/wasm32-unknown-unknown/release/dao_token.wasm");
 }
 
-pub fn get_dao_token_client(env: &Env) -> tokenclient::Client {
-    let token_id: BytesN<32> = env
-        .storage()
+pub fn get_dao_token_id(env: &Env) -> BytesN<32> {
+    env.storage()
         .get(&DataKey::DaoToken)
         .unwrap() // we don't handle error here. If this doesn't work, then we are screwed anyways.
-        .unwrap();
+        .unwrap()
+}
 
-    tokenclient::Client::new(&env, &token_id)
+pub fn get_dao_token_client(env: &Env) -> tokenclient::Client {
+    tokenclient::Client::new(&env, &get_dao_token_id(env))
 }
 
 pub fn store_dao_token(env: &Env, token_id: BytesN<32>) {
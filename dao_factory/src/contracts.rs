@@ -0,0 +1,11 @@
+pub mod tokenclient {
+    soroban_sdk::contractimport!(file = "../target/wasm32-unknown-unknown/release/dao_token.wasm");
+}
+
+// the generated client has the same arguments as the dao `init`
+#[allow(clippy::too_many_arguments)]
+pub mod daoclient {
+    soroban_sdk::contractimport!(
+        file = "../target/wasm32-unknown-unknown/release/dao_with_delagate.wasm"
+    );
+}
@@ -0,0 +1,74 @@
+use soroban_sdk::{contracttype, panic_with_error, unwrap::UnwrapOptimized, Address, BytesN, Env};
+
+use crate::{errors::ContractError, DaoInfo};
+
+#[derive(Clone)]
+#[contracttype]
+pub enum DataKey {
+    // address that initialized the factory
+    Admin,
+    // wasm hash of the dao token
+    TokenWasm,
+    // wasm hash of the dao
+    DaoWasm,
+    // amount of daos created by this factory
+    // u32
+    DaoCount,
+    // -> DaoInfo struct, of the dao created with this index
+    Dao(u32),
+}
+
+pub fn is_init(env: &Env) -> bool {
+    env.storage().has(&DataKey::DaoWasm)
+}
+
+pub fn set_admin(env: &Env, admin: Address) {
+    env.storage().set(&DataKey::Admin, &admin)
+}
+
+pub fn get_admin(env: &Env) -> Address {
+    env.storage()
+        .get(&DataKey::Admin)
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::NotInit))
+        .unwrap_optimized()
+}
+
+pub fn set_wasm_hashes(env: &Env, token_wasm: BytesN<32>, dao_wasm: BytesN<32>) {
+    env.storage().set(&DataKey::TokenWasm, &token_wasm);
+    env.storage().set(&DataKey::DaoWasm, &dao_wasm);
+}
+
+pub fn get_token_wasm(env: &Env) -> BytesN<32> {
+    env.storage()
+        .get(&DataKey::TokenWasm)
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::NotInit))
+        .unwrap_optimized()
+}
+
+pub fn get_dao_wasm(env: &Env) -> BytesN<32> {
+    env.storage()
+        .get(&DataKey::DaoWasm)
+        .unwrap_or_else(|| panic_with_error!(env, ContractError::NotInit))
+        .unwrap_optimized()
+}
+
+pub fn get_dao_count(env: &Env) -> u32 {
+    env.storage()
+        .get(&DataKey::DaoCount)
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+pub fn get_dao(env: &Env, index: u32) -> DaoInfo {
+    env.storage()
+        .get(&DataKey::Dao(index))
+        .unwrap_optimized()
+        .unwrap_optimized()
+}
+
+// one entry per dao, so creating a dao costs the same however many there are
+pub fn add_dao(env: &Env, dao: DaoInfo) {
+    let count = get_dao_count(env);
+    env.storage().set(&DataKey::Dao(count), &dao);
+    env.storage().set(&DataKey::DaoCount, &(count + 1))
+}
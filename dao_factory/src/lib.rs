@@ -0,0 +1,156 @@
+#![no_std]
+
+mod contracts;
+mod data_keys;
+mod errors;
+mod test;
+
+use contracts::{daoclient, tokenclient};
+use data_keys::{
+    add_dao, get_admin, get_dao, get_dao_count, get_dao_wasm, get_token_wasm, is_init, set_admin,
+    set_wasm_hashes,
+};
+use errors::ContractError;
+use soroban_sdk::{contractimpl, contracttype, panic_with_error, Address, Bytes, BytesN, Env, Vec};
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DaoInfo {
+    pub dao: BytesN<32>,
+    pub token: BytesN<32>,
+}
+
+// tokens minted to `to` when the dao is created
+#[contracttype]
+#[derive(Clone)]
+pub struct Allocation {
+    pub to: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct DaoParams {
+    // token metadata
+    pub decimal: u32,
+    pub name: Bytes,
+    pub symbol: Bytes,
+    // holders get voting power without delegating
+    pub auto_power: bool,
+    // same as the arguments of the dao `init`
+    pub time_model: daoclient::TimeModel,
+    pub min_dur: u32,
+    pub max_dur: u32,
+    pub grace: u32,
+    pub quorum: u32,
+    pub min_prop_p: i128,
+}
+
+pub trait FactoryTrait {
+    // set the wasm hashes of the token and dao that this factory deploys.
+    // `admin` has to sign it, so check `admin` before using a factory that someone else
+    // could have initialized first.
+    fn init(env: Env, admin: Address, token_wasm: BytesN<32>, dao_wasm: BytesN<32>);
+
+    // address that initialized the factory
+    fn admin(env: Env) -> Address;
+
+    // deploy a token and a dao that is its admin, and mint the initial distribution.
+    // `salt` has to be unique for every dao created by this factory.
+    fn create(
+        env: Env,
+        salt: BytesN<32>,
+        params: DaoParams,
+        distribution: Vec<Allocation>,
+    ) -> DaoInfo;
+
+    // amount of daos created by this factory
+    fn dao_count(env: Env) -> u32;
+
+    // at most `limit` daos, starting at index `start`
+    fn daos(env: Env, start: u32, limit: u32) -> Vec<DaoInfo>;
+}
+
+pub struct FactoryContract;
+
+#[contractimpl]
+impl FactoryTrait for FactoryContract {
+    fn init(env: Env, admin: Address, token_wasm: BytesN<32>, dao_wasm: BytesN<32>) {
+        if is_init(&env) {
+            panic_with_error!(&env, ContractError::AlreadyInit)
+        }
+        admin.require_auth();
+        set_admin(&env, admin);
+        set_wasm_hashes(&env, token_wasm, dao_wasm);
+    }
+
+    fn admin(env: Env) -> Address {
+        get_admin(&env)
+    }
+
+    fn create(
+        env: Env,
+        salt: BytesN<32>,
+        params: DaoParams,
+        distribution: Vec<Allocation>,
+    ) -> DaoInfo {
+        // the token and dao need different salts
+        let dao_salt = env.crypto().sha256(&salt.clone().into());
+
+        let deployer = env.deployer();
+        let token_id = deployer
+            .with_current_contract(&salt)
+            .deploy(&get_token_wasm(&env));
+        let dao_id = deployer
+            .with_current_contract(&dao_salt)
+            .deploy(&get_dao_wasm(&env));
+
+        // the factory is the token admin until the distribution is minted
+        let factory = env.current_contract_address();
+        let token_client = tokenclient::Client::new(&env, &token_id);
+        token_client.initialize(&factory, &params.decimal, &params.name, &params.symbol);
+        if params.auto_power {
+            token_client.set_auto(&factory, &true);
+        }
+
+        for allocation in distribution.iter() {
+            let allocation = allocation.unwrap();
+            if allocation.amount < 0 {
+                panic_with_error!(&env, ContractError::CannotMintNegative)
+            }
+            token_client.mint(&factory, &allocation.to, &allocation.amount);
+        }
+
+        let dao_client = daoclient::Client::new(&env, &dao_id);
+        token_client.set_admin(&factory, &dao_client.address());
+        dao_client.init(
+            &token_id,
+            &params.time_model,
+            &params.min_dur,
+            &params.max_dur,
+            &params.grace,
+            &params.quorum,
+            &params.min_prop_p,
+        );
+
+        let info = DaoInfo {
+            dao: dao_id,
+            token: token_id,
+        };
+        add_dao(&env, info.clone());
+        info
+    }
+
+    fn dao_count(env: Env) -> u32 {
+        get_dao_count(&env)
+    }
+
+    fn daos(env: Env, start: u32, limit: u32) -> Vec<DaoInfo> {
+        let end = get_dao_count(&env).min(start.saturating_add(limit));
+        let mut daos = Vec::new(&env);
+        for index in start..end {
+            daos.push_back(get_dao(&env, index))
+        }
+        daos
+    }
+}
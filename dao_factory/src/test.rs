@@ -0,0 +1,110 @@
+#![cfg(test)]
+
+extern crate std;
+
+use crate::contracts::{daoclient, tokenclient};
+use crate::errors::ContractError;
+use crate::{Allocation, DaoParams, FactoryContract, FactoryContractClient};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{symbol, vec, Address, Bytes, BytesN, Env, IntoVal};
+
+fn params(env: &Env) -> DaoParams {
+    DaoParams {
+        decimal: 7,
+        name: Bytes::from_array(env, b"DAO TOKEN"),
+        symbol: Bytes::from_array(env, b"DTOKEN"),
+        auto_power: true,
+        time_model: daoclient::TimeModel::Timestamp,
+        min_dur: 3600,
+        max_dur: 604800,
+        grace: 604800,
+        quorum: 10,
+        min_prop_p: 1,
+    }
+}
+
+#[test]
+fn test() {
+    let env: Env = Default::default();
+    let factory_client =
+        FactoryContractClient::new(&env, &env.register_contract(None, FactoryContract));
+    factory_client.init(
+        &Address::random(&env),
+        &env.install_contract_wasm(tokenclient::WASM),
+        &env.install_contract_wasm(daoclient::WASM),
+    );
+
+    let user_1 = Address::random(&env);
+    let user_2 = Address::random(&env);
+
+    let info = factory_client.create(
+        &BytesN::from_array(&env, &[0; 32]),
+        &params(&env),
+        &vec![
+            &env,
+            Allocation {
+                to: user_1.clone(),
+                amount: 100,
+            },
+            Allocation {
+                to: user_2.clone(),
+                amount: 50,
+            },
+        ],
+    );
+
+    let token_client = tokenclient::Client::new(&env, &info.token);
+    let dao_client = daoclient::Client::new(&env, &info.dao);
+
+    assert_eq!(dao_client.address(), token_client.admin());
+    assert_eq!(100, token_client.balance(&user_1));
+    assert_eq!(50, token_client.balance(&user_2));
+    assert_eq!(150, token_client.supply());
+    assert_eq!(100, token_client.power(&user_1));
+    assert_eq!(Bytes::from_array(&env, b"DTOKEN"), token_client.symbol());
+    assert_eq!(3600, dao_client.min_dur());
+    assert_eq!(604800, dao_client.max_dur());
+    assert_eq!(10, dao_client.quorum());
+
+    let second = factory_client.create(
+        &BytesN::from_array(&env, &[1; 32]),
+        &params(&env),
+        &vec![&env],
+    );
+
+    assert_eq!(2, factory_client.dao_count());
+    assert_eq!(
+        vec![&env, info.clone(), second.clone()],
+        factory_client.daos(&0, &10)
+    );
+    assert_eq!(vec![&env, second], factory_client.daos(&1, &1));
+    assert_eq!(0, factory_client.daos(&2, &1).len());
+}
+
+#[test]
+fn test_init_once() {
+    let env: Env = Default::default();
+    let factory_client =
+        FactoryContractClient::new(&env, &env.register_contract(None, FactoryContract));
+    let token_wasm = env.install_contract_wasm(tokenclient::WASM);
+    let dao_wasm = env.install_contract_wasm(daoclient::WASM);
+
+    let admin = Address::random(&env);
+
+    // the deployer signs, and can check that it was the one to initialize the factory
+    factory_client.init(&admin, &token_wasm, &dao_wasm);
+    assert_eq!(
+        env.recorded_top_authorizations(),
+        std::vec![(
+            admin.clone(),
+            factory_client.contract_id.clone(),
+            symbol!("init"),
+            (&admin, token_wasm.clone(), dao_wasm.clone()).into_val(&env),
+        )]
+    );
+    assert_eq!(admin, factory_client.admin());
+    assert_eq!(
+        factory_client.try_init(&Address::random(&env), &token_wasm, &dao_wasm),
+        Err(Ok(ContractError::AlreadyInit.into()))
+    );
+}
@@ -1,14 +1,18 @@
+use crate::dao::TokenError;
 use crate::storage_types::DataKey;
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env};
 
 pub fn has_administrator(e: &Env) -> bool {
     let key = DataKey::Admin;
     e.storage().has(&key)
 }
 
-fn read_administrator(e: &Env) -> Address {
+pub fn read_administrator(e: &Env) -> Address {
     let key = DataKey::Admin;
-    e.storage().get_unchecked(&key).unwrap()
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, TokenError::NotInitialized))
+        .unwrap()
 }
 
 pub fn write_administrator(e: &Env, id: &Address) {
@@ -18,6 +22,6 @@ pub fn write_administrator(e: &Env, id: &Address) {
 
 pub fn check_admin(e: &Env, admin: &Address) {
     if admin != &read_administrator(e) {
-        panic!("not authorized by admin")
+        panic_with_error!(e, TokenError::NotAdmin)
     }
 }
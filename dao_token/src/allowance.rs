@@ -1,5 +1,6 @@
+use crate::dao::TokenError;
 use crate::storage_types::{AllowanceDataKey, DataKey};
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env};
 
 pub fn read_allowance(e: &Env, from: Address, spender: Address) -> i128 {
     let key = DataKey::Allowance(AllowanceDataKey { from, spender });
@@ -18,7 +19,7 @@ pub fn write_allowance(e: &Env, from: Address, spender: Address, amount: i128) {
 pub fn spend_allowance(e: &Env, from: Address, spender: Address, amount: i128) {
     let allowance = read_allowance(e, from.clone(), spender.clone());
     if allowance < amount {
-        panic!("insufficient allowance");
+        panic_with_error!(e, TokenError::InsufficientAllowance);
     }
     write_allowance(e, from, spender, allowance - amount);
 }
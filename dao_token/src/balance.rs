@@ -1,5 +1,6 @@
+use crate::dao::{move_live_power, TokenError};
 use crate::storage_types::DataKey;
-use soroban_sdk::{Address, Env};
+use soroban_sdk::{panic_with_error, Address, Env};
 
 pub fn read_balance(e: &Env, addr: Address) -> i128 {
     let key = DataKey::Balance(addr);
@@ -16,9 +17,15 @@ fn write_balance(e: &Env, addr: Address, amount: i128) {
 }
 
 pub fn receive_balance(e: &Env, addr: Address, amount: i128) {
+    add_to_balance(e, addr.clone(), amount);
+    move_live_power(e, addr, amount);
+}
+
+// like `receive_balance`, for callers that move the power of the live delegate themselves
+pub fn add_to_balance(e: &Env, addr: Address, amount: i128) {
     let balance = read_balance(e, addr.clone());
     if !is_authorized(e, addr.clone()) {
-        panic!("can't receive when deauthorized");
+        panic_with_error!(e, TokenError::ReceiveDeauthorized);
     }
     write_balance(e, addr, balance + amount);
 }
@@ -26,12 +33,13 @@ pub fn receive_balance(e: &Env, addr: Address, amount: i128) {
 pub fn spend_balance(e: &Env, addr: Address, amount: i128) {
     let balance = read_balance(e, addr.clone());
     if !is_authorized(e, addr.clone()) {
-        panic!("can't spend when deauthorized");
+        panic_with_error!(e, TokenError::SpendDeauthorized);
     }
     if balance < amount {
-        panic!("insufficient balance");
+        panic_with_error!(e, TokenError::InsufficientBalance);
     }
-    write_balance(e, addr, balance - amount);
+    write_balance(e, addr.clone(), balance - amount);
+    move_live_power(e, addr, -amount);
 }
 
 pub fn is_authorized(e: &Env, addr: Address) -> bool {
@@ -47,3 +55,22 @@ pub fn write_authorization(e: &Env, addr: Address, is_authorized: bool) {
     let key = DataKey::State(addr);
     e.storage().set(&key, &is_authorized);
 }
+
+pub fn read_supply(e: &Env) -> i128 {
+    let key = DataKey::Supply;
+    if let Some(supply) = e.storage().get(&key) {
+        supply.unwrap()
+    } else {
+        0
+    }
+}
+
+pub fn increase_supply(e: &Env, amount: i128) {
+    let key = DataKey::Supply;
+    e.storage().set(&key, &(read_supply(e) + amount));
+}
+
+pub fn decrease_supply(e: &Env, amount: i128) {
+    let key = DataKey::Supply;
+    e.storage().set(&key, &(read_supply(e) - amount));
+}
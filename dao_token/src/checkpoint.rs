@@ -0,0 +1,219 @@
+use soroban_sdk::{contracttype, unwrap::UnwrapOptimized, Address, Env, Vec};
+
+use crate::dao::{DaoDataKey, PowerAtArgs};
+
+// the `index`th power checkpoint of `ident`
+#[derive(Clone)]
+#[contracttype]
+pub struct CheckpointKey {
+    pub ident: Address,
+    pub index: u32,
+}
+
+// power at the end of `block`, until the next checkpoint. `time` is the timestamp of `block`,
+// which the old history doesn't have, so it is 0 in migrated checkpoints.
+// `live` is the part of `power` that flows in from live delegators. The old history doesn't
+// have it, so migrated checkpoints count all of their power as live.
+// `timed` is everything delegated with `dlg_until` up to `block`, which isn't part of `power`.
+// What ended is only subtracted when it is looked up, from the amounts in `expiry`.
+#[derive(Clone, Default)]
+#[contracttype]
+pub struct Checkpoint {
+    pub block: u32,
+    pub time: u64,
+    pub power: i128,
+    pub live: i128,
+    pub timed: i128,
+}
+
+pub fn get_checkpoint_count(env: &Env, of: Address) -> u32 {
+    env.storage()
+        .get(&DaoDataKey::CpCount(of))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+fn get_checkpoint(env: &Env, ident: Address, index: u32) -> Checkpoint {
+    env.storage()
+        .get(&DaoDataKey::Checkpoint(CheckpointKey { ident, index }))
+        .unwrap_optimized()
+        .unwrap_optimized()
+}
+
+fn set_checkpoint(env: &Env, ident: Address, index: u32, checkpoint: &Checkpoint) {
+    env.storage().set(
+        &DaoDataKey::Checkpoint(CheckpointKey { ident, index }),
+        checkpoint,
+    )
+}
+
+// store a change of the values of the last checkpoint at the current block, and return them.
+// Only the last checkpoint is read, so this costs the same however long the history is.
+pub fn add_checkpoint(env: &Env, of: Address, update: impl FnOnce(&mut Checkpoint)) -> Checkpoint {
+    migrate_checkpoints(env, of.clone());
+
+    let block = env.ledger().sequence();
+    let count = get_checkpoint_count(env, of.clone());
+    let mut checkpoint = if count > 0 {
+        get_checkpoint(env, of.clone(), count - 1)
+    } else {
+        Checkpoint::default()
+    };
+    // one checkpoint per block, with the values at its end
+    let index = if count > 0 && checkpoint.block == block {
+        count - 1
+    } else {
+        env.storage()
+            .set(&DaoDataKey::CpCount(of.clone()), &(count + 1));
+        count
+    };
+    checkpoint.block = block;
+    checkpoint.time = env.ledger().timestamp();
+    update(&mut checkpoint);
+    set_checkpoint(env, of, index, &checkpoint);
+    checkpoint
+}
+
+// reads one checkpoint per step of the binary search
+pub fn get_checkpoint_at_or_before(
+    env: &Env,
+    of: Address,
+    at_or_before: u32,
+) -> Option<Checkpoint> {
+    if env.storage().has(&DaoDataKey::PChanges(of.clone())) {
+        return legacy_checkpoint_at_or_before(env, of, at_or_before);
+    }
+    search_checkpoints(env, of, |checkpoint| checkpoint.block <= at_or_before)
+}
+
+pub fn get_power_at_or_before(env: &Env, of: Address, at_or_before: u32) -> i128 {
+    get_checkpoint_at_or_before(env, of, at_or_before).map_or(0, |checkpoint| checkpoint.power)
+}
+
+// block of the first checkpoint of `of` with a timestamp after `at_time`, before which the
+// ledger at `at_time` was. None if all of them are at or before it.
+pub fn get_block_after_time(env: &Env, of: Address, at_time: u64) -> Option<u32> {
+    let count = get_checkpoint_count(env, of.clone());
+    let index = search(count, |index| {
+        get_checkpoint(env, of.clone(), index).time <= at_time
+    })
+    .map_or(0, |index| index + 1);
+    if index == count {
+        return None;
+    }
+    Some(get_checkpoint(env, of, index).block)
+}
+
+// the last checkpoint that `is_before` holds for.
+// `is_before` has to hold for a prefix of the checkpoints.
+fn search_checkpoints(
+    env: &Env,
+    of: Address,
+    is_before: impl Fn(&Checkpoint) -> bool,
+) -> Option<Checkpoint> {
+    search(get_checkpoint_count(env, of.clone()), |index| {
+        is_before(&get_checkpoint(env, of.clone(), index))
+    })
+    .map(|index| get_checkpoint(env, of, index))
+}
+
+// index of the last of `count` items that `is_before` holds for, with a binary search.
+// `is_before` has to hold for a prefix of the items.
+pub fn search(count: u32, is_before: impl Fn(u32) -> bool) -> Option<u32> {
+    if count == 0 {
+        return None;
+    }
+    // most lookups are for the current power or a recent proposal
+    if is_before(count - 1) {
+        return Some(count - 1);
+    }
+
+    // first item after the searched point, which the last one is
+    let (mut low, mut high) = (0, count - 1);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if is_before(mid) {
+            low = mid + 1
+        } else {
+            high = mid
+        }
+    }
+    low.checked_sub(1)
+}
+
+// latest block of `changes` that is at or before `at_or_before`
+pub fn latest_change(changes: &Vec<u32>, at_or_before: u32) -> Option<u32> {
+    if changes.len() == 0 {
+        return None;
+    }
+    let res = changes.binary_search(at_or_before);
+
+    if let Ok(_) = res {
+        // `at_or_before` sequence is in list
+        Some(at_or_before)
+    } else {
+        // index is the index where the new element should be inserted in the vec, so that it stays sorted.
+        // This mean that to actually get the value we want we neec to do vec[index-1]
+        let index = unsafe { res.unwrap_err_unchecked() };
+        if index == 0 {
+            return None;
+        }
+        Some(changes.get(index - 1).unwrap_optimized().unwrap_optimized())
+    }
+}
+
+// the blocks at which the power changed, of a history that wasn't migrated yet
+fn get_legacy_changes(env: &Env, of: Address) -> Option<Vec<u32>> {
+    env.storage()
+        .get(&DaoDataKey::PChanges(of))
+        .map(|changes| changes.unwrap_optimized())
+}
+
+fn legacy_checkpoint_at_or_before(env: &Env, of: Address, at_or_before: u32) -> Option<Checkpoint> {
+    let changes = get_legacy_changes(env, of.clone()).unwrap_optimized();
+    let block = latest_change(&changes, at_or_before)?;
+    let power = env
+        .storage()
+        .get(&DaoDataKey::PowerAt(PowerAtArgs { block, ident: of }))
+        .unwrap_optimized()
+        .unwrap_optimized();
+    Some(legacy_checkpoint(block, power))
+}
+
+fn legacy_checkpoint(block: u32, power: i128) -> Checkpoint {
+    Checkpoint {
+        block,
+        time: 0,
+        power,
+        live: power,
+        timed: 0,
+    }
+}
+
+// move a history stored in `PChanges` and `PowerAt` to checkpoints.
+// Does nothing if there is no such history.
+pub fn migrate_checkpoints(env: &Env, of: Address) {
+    let changes = match get_legacy_changes(env, of.clone()) {
+        Some(changes) => changes,
+        None => return,
+    };
+
+    let mut index = 0;
+    for block in changes.iter() {
+        let block = block.unwrap_optimized();
+        let key = DaoDataKey::PowerAt(PowerAtArgs {
+            block,
+            ident: of.clone(),
+        });
+        let power = env
+            .storage()
+            .get(&key)
+            .unwrap_optimized()
+            .unwrap_optimized();
+        env.storage().remove(&key);
+        set_checkpoint(env, of.clone(), index, &legacy_checkpoint(block, power));
+        index += 1;
+    }
+    env.storage().set(&DaoDataKey::CpCount(of.clone()), &index);
+    env.storage().remove(&DaoDataKey::PChanges(of));
+}
@@ -1,14 +1,17 @@
 //! This contract demonstrates a sample implementation of the Soroban token
 //! interface.
-use crate::admin::{check_admin, has_administrator, write_administrator};
+use crate::admin::{check_admin, has_administrator, read_administrator, write_administrator};
 use crate::allowance::{read_allowance, spend_allowance, write_allowance};
+use crate::balance::{
+    decrease_supply, increase_supply, read_balance, read_supply, receive_balance, spend_balance,
+};
 use crate::balance::{is_authorized, write_authorization};
-use crate::balance::{read_balance, receive_balance, spend_balance};
+use crate::dao::TokenError;
 use crate::event;
 use crate::metadata::{
     read_decimal, read_name, read_symbol, write_decimal, write_name, write_symbol,
 };
-use soroban_sdk::{contractimpl, Address, Bytes, Env};
+use soroban_sdk::{contractimpl, panic_with_error, Address, Bytes, Env};
 
 pub trait TokenTrait {
     fn initialize(e: Env, admin: Address, decimal: u32, name: Bytes, symbol: Bytes);
@@ -21,6 +24,8 @@ pub trait TokenTrait {
 
     fn balance(e: Env, id: Address) -> i128;
 
+    fn supply(e: Env) -> i128;
+
     fn spendable(e: Env, id: Address) -> i128;
 
     fn authorized(e: Env, id: Address) -> bool;
@@ -41,6 +46,8 @@ pub trait TokenTrait {
 
     fn set_admin(e: Env, admin: Address, new_admin: Address);
 
+    fn admin(e: Env) -> Address;
+
     fn decimals(e: Env) -> u32;
 
     fn name(e: Env) -> Bytes;
@@ -48,9 +55,9 @@ pub trait TokenTrait {
     fn symbol(e: Env) -> Bytes;
 }
 
-fn check_nonnegative_amount(amount: i128) {
+fn check_nonnegative_amount(e: &Env, amount: i128) {
     if amount < 0 {
-        panic!("negative amount is not allowed: {}", amount)
+        panic_with_error!(e, TokenError::NegativeAmount)
     }
 }
 
@@ -60,11 +67,13 @@ pub struct Token;
 impl TokenTrait for Token {
     fn initialize(e: Env, admin: Address, decimal: u32, name: Bytes, symbol: Bytes) {
         if has_administrator(&e) {
-            panic!("already initialized")
+            panic_with_error!(&e, TokenError::AlreadyInitialized)
         }
         write_administrator(&e, &admin);
 
-        write_decimal(&e, u8::try_from(decimal).expect("Decimal must fit in a u8"));
+        let decimal = u8::try_from(decimal)
+            .unwrap_or_else(|_| panic_with_error!(&e, TokenError::DecimalTooLarge));
+        write_decimal(&e, decimal);
         write_name(&e, name);
         write_symbol(&e, symbol);
     }
@@ -76,12 +85,12 @@ impl TokenTrait for Token {
     fn incr_allow(e: Env, from: Address, spender: Address, amount: i128) {
         from.require_auth();
 
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
 
         let allowance = read_allowance(&e, from.clone(), spender.clone());
         let new_allowance = allowance
             .checked_add(amount)
-            .expect("Updated allowance doesn't fit in an i128");
+            .unwrap_or_else(|| panic_with_error!(&e, TokenError::AllowanceOverflow));
 
         write_allowance(&e, from.clone(), spender.clone(), new_allowance);
         event::incr_allow(&e, from, spender, amount);
@@ -90,7 +99,7 @@ impl TokenTrait for Token {
     fn decr_allow(e: Env, from: Address, spender: Address, amount: i128) {
         from.require_auth();
 
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
 
         let allowance = read_allowance(&e, from.clone(), spender.clone());
         if amount >= allowance {
@@ -105,6 +114,10 @@ impl TokenTrait for Token {
         read_balance(&e, id)
     }
 
+    fn supply(e: Env) -> i128 {
+        read_supply(&e)
+    }
+
     fn spendable(e: Env, id: Address) -> i128 {
         read_balance(&e, id)
     }
@@ -116,7 +129,7 @@ impl TokenTrait for Token {
     fn xfer(e: Env, from: Address, to: Address, amount: i128) {
         from.require_auth();
 
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
         spend_balance(&e, from.clone(), amount);
         receive_balance(&e, to.clone(), amount);
         event::transfer(&e, from, to, amount);
@@ -125,7 +138,7 @@ impl TokenTrait for Token {
     fn xfer_from(e: Env, spender: Address, from: Address, to: Address, amount: i128) {
         spender.require_auth();
 
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
         spend_allowance(&e, from.clone(), spender, amount);
         spend_balance(&e, from.clone(), amount);
         receive_balance(&e, to.clone(), amount);
@@ -135,25 +148,28 @@ impl TokenTrait for Token {
     fn burn(e: Env, from: Address, amount: i128) {
         from.require_auth();
 
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         event::burn(&e, from, amount);
     }
 
     fn burn_from(e: Env, spender: Address, from: Address, amount: i128) {
         spender.require_auth();
 
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
         spend_allowance(&e, from.clone(), spender, amount);
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         event::burn(&e, from, amount)
     }
 
     fn clawback(e: Env, admin: Address, from: Address, amount: i128) {
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
         check_admin(&e, &admin);
         admin.require_auth();
         spend_balance(&e, from.clone(), amount);
+        decrease_supply(&e, amount);
         event::clawback(&e, admin, from, amount);
     }
 
@@ -165,10 +181,11 @@ impl TokenTrait for Token {
     }
 
     fn mint(e: Env, admin: Address, to: Address, amount: i128) {
-        check_nonnegative_amount(amount);
+        check_nonnegative_amount(&e, amount);
         check_admin(&e, &admin);
         admin.require_auth();
         receive_balance(&e, to.clone(), amount);
+        increase_supply(&e, amount);
         event::mint(&e, admin, to, amount);
     }
 
@@ -179,6 +196,10 @@ impl TokenTrait for Token {
         event::set_admin(&e, admin, new_admin);
     }
 
+    fn admin(e: Env) -> Address {
+        read_administrator(&e)
+    }
+
     fn decimals(e: Env) -> u32 {
         read_decimal(&e)
     }
@@ -2,15 +2,22 @@ use core::ops::Add;
 
 use soroban_sdk::{
     contracterror, contractimpl, contracttype, panic_with_error, symbol, unwrap::UnwrapOptimized,
-    Address, Env, Vec,
+    Address, Env, Map, Vec,
 };
 
-use crate::balance::{receive_balance, spend_balance};
+use crate::admin::check_admin;
+use crate::balance::{add_to_balance, read_balance, read_supply, receive_balance, spend_balance};
+use crate::checkpoint::{
+    add_checkpoint, get_block_after_time, get_checkpoint_at_or_before, get_power_at_or_before,
+    migrate_checkpoints, search, Checkpoint, CheckpointKey,
+};
+use crate::event;
+use crate::expiry::{add_expiry, get_expired, ExpiryKey};
 #[derive(Clone)]
 #[contracttype]
 pub struct PowerAtArgs {
-    block: u32,
-    ident: Address,
+    pub block: u32,
+    pub ident: Address,
 }
 #[derive(Clone)]
 #[contracttype]
@@ -19,22 +26,137 @@ pub struct DelegateAmountArgs {
     to: Address,
 }
 
+#[derive(Clone)]
+#[contracttype]
+pub struct HeightKey {
+    pub of: Address,
+    pub height: u32,
+}
+
+// tokens locked with `delegate` from, to to
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Delegation {
+    pub from: Address,
+    pub to: Address,
+    pub amount: i128,
+}
+
+// tokens locked with `dlg_until` from, to to. The power counts for ledgers `start` up to
+// but excluding `end`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct TimedDelegation {
+    pub from: Address,
+    pub to: Address,
+    pub amount: i128,
+    pub start: u32,
+    pub end: u32,
+}
+
+// the live delegate from `block` on, the address itself if it had none
+#[derive(Clone)]
+#[contracttype]
+pub struct DelegateAt {
+    pub block: u32,
+    pub delegate: Address,
+}
+
+// one of the lists of delegations of an address, which store the address at the other end
+#[derive(Clone)]
+#[contracttype]
+pub enum EdgeList {
+    // made by this address with `delegate`
+    Out(Address),
+    // made to this address with `delegate`
+    In(Address),
+    // made by this address with `dlg_until`
+    TimedOut(Address),
+    // made to this address with `dlg_until`
+    TimedIn(Address),
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct EdgeKey {
+    pub list: EdgeList,
+    pub index: u32,
+}
+
+#[derive(Clone)]
+#[contracttype]
+pub struct EdgeOf {
+    pub list: EdgeList,
+    pub other: Address,
+}
+
+#[derive(Clone)]
 #[contracttype]
 pub enum DaoDataKey {
-    // blocks where their voting power changed
+    // blocks where their voting power changed. Replaced by `Checkpoint`, and removed by
+    // `migrate_cp` or the next power change.
     // Vec<u64>
     PChanges(Address),
-    // power at block
+    // power at block. Replaced by `Checkpoint` like `PChanges`.
     //u128
     PowerAt(PowerAtArgs),
+    // number of power checkpoints
+    // u32
+    CpCount(Address),
+    // -> Checkpoint struct
+    Checkpoint(CheckpointKey),
     //u128
     //current power
     Power(Address),
     // amount delegated from, to to
     // i128
     DelegateTo(DelegateAmountArgs),
+    // delegate that gets the power of the whole balance of this address
+    // Address
+    LiveDlg(Address),
+    // whether holders without a live delegate get the power of their own balance
+    // bool
+    AutoSelf,
+    // number of live delegators of this address, not counting itself, with a height
+    // u32
+    HCount(HeightKey),
+    // longest chain of live delegations that ends at this address
+    // u32
+    Height(Address),
+    // number of changes of the live delegate
+    // u32
+    DlgCount(Address),
+    // -> DelegateAt struct, the `index`th change of the live delegate
+    DlgAt(CheckpointKey),
+    // longest chain of live delegations that power flows through
+    // u32
+    MaxDepth,
+    // where the tree of the amounts delegated with `dlg_until` to this address starts,
+    // and how far it goes
+    // ExpiryTree
+    ExpTree(Address),
+    // node of that tree, with the amounts by the ledger they stop counting at
+    // i128
+    Expiry(ExpiryKey),
+    // the time-bounded delegation from, to to
+    // TimedDelegation
+    TimedDlg(DelegateAmountArgs),
+    // number of delegations in the list
+    // u32
+    EdgeCount(EdgeList),
+    // address at the other end of a delegation in the list
+    // Address
+    Edge(EdgeKey),
+    // where in the list the delegation with an address is
+    // u32
+    EdgeIndex(EdgeOf),
 }
 
+const DEFAULT_MAX_DEPTH: u32 = 3;
+
+// change of the power of each address, and of the part of it from live delegators
+type PowerChanges = Map<Address, (i128, i128)>;
+
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 #[repr(u32)]
@@ -45,21 +167,99 @@ pub enum DaoError {
     CannotAddNegativePower = 3,
     CannotRemoveNegativePower = 4,
     IncorrectNonce = 5,
+    SupplyNotZero = 6,
+    DelegationCycle = 7,
+    ChainTooDeep = 8,
+    ExpiryInPast = 9,
+    DelegationExists = 10,
+    NoDelegation = 11,
+    AmountNotPositive = 12,
+}
+
+// errors of the token functions. The codes start at 100 so they don't overlap with `DaoError`.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum TokenError {
+    NotInitialized = 100,
+    AlreadyInitialized = 101,
+    DecimalTooLarge = 102,
+    NegativeAmount = 103,
+    NotAdmin = 104,
+    InsufficientBalance = 105,
+    InsufficientAllowance = 106,
+    AllowanceOverflow = 107,
+    ReceiveDeauthorized = 108,
+    SpendDeauthorized = 109,
 }
 
 pub trait DaoExtensionTrait {
     // Get voting power of a Identifier
     // We explicitly use Identifier instead of Address to allow for threshold signature schemes like FROST
+    // Includes its balance if it has a live delegate, and the power of its live delegators,
+    // along the whole chain. All of it flows on to its own live delegate.
+    // Also includes what was delegated with `dlg_until` and didn't expire yet.
     fn power(env: Env, of: Address) -> i128;
     fn power_at(env: Env, of: Address, at_block: u32) -> i128;
+    // `power_at` the ledger before the first checkpoint of `of` with a timestamp after `at_time`,
+    // which is the last ledger that can be at or before it. The ledgers between two checkpoints
+    // have no timestamps, so a `dlg_until` delegation that ends between them counts as ended
+    // right after the first one. The migrated history has no timestamps at all, so its last
+    // power counts for any time before the first checkpoint with one.
+    fn power_at_t(env: Env, of: Address, at_time: u64) -> i128;
     // delegate power `from` to `to`
     fn delegate(env: Env, from: Address, to: Address, amount: i128);
     // remove delegation
     // amount is the amount we want to remove
     // from is the person who delegated
     fn r_delegate(env: Env, from: Address, to: Address, amount: i128);
+    // move `amount` that `from` delegated to `old_to` over to `new_to`, without unlocking it
+    fn redelegate(env: Env, from: Address, old_to: Address, new_to: Address, amount: i128);
+    // remove all delegations of `from` made with `delegate`
+    fn r_dlg_all(env: Env, from: Address);
     //get amount that `from` has delegated to `to`
     fn get_d_a(env: Env, from: Address, to: Address) -> i128;
+    // delegations made by `from` with `delegate`, at most `limit` starting at index `start`
+    fn dlgs_from(env: Env, from: Address, start: u32, limit: u32) -> Vec<Delegation>;
+    // delegations to `to` made with `delegate`, at most `limit` starting at index `start`
+    fn dlgtors_of(env: Env, to: Address, start: u32, limit: u32) -> Vec<Delegation>;
+    // like `delegate`, but the power stops counting for `to` from `expiry` on.
+    // Only one such delegation from, to to can exist at a time. It doesn't flow on through
+    // live delegations, it only counts for `to`.
+    fn dlg_until(env: Env, from: Address, to: Address, amount: i128, expiry: u32);
+    // give the tokens of a `dlg_until` delegation back to `from`. Ends it early if it hasn't expired.
+    fn reclaim(env: Env, from: Address, to: Address);
+    // delegations made by `from` with `dlg_until`, at most `limit` starting at index `start`.
+    // Expired ones are listed until they are reclaimed.
+    fn timed_from(env: Env, from: Address, start: u32, limit: u32) -> Vec<TimedDelegation>;
+    // delegations to `to` made with `dlg_until`, like `timed_from`
+    fn timed_to(env: Env, to: Address, start: u32, limit: u32) -> Vec<TimedDelegation>;
+    // part of `power_at` that was delegated with `dlg_until`
+    fn timed_at(env: Env, of: Address, at_block: u32) -> i128;
+    // part of `power_at` that flows in from live delegators. They can still vote with it
+    // themselves, which takes it back from `of`.
+    fn live_at(env: Env, of: Address, at_block: u32) -> i128;
+    // give the power of `from`, with its whole balance, to `to`, without locking the tokens.
+    // The power follows the balance when it changes. Replaces the previous live delegate.
+    fn live_dlg(env: Env, from: Address, to: Address);
+    // stop the live delegation of `from`
+    fn r_live_dlg(env: Env, from: Address);
+    // live delegate of `of`, if any
+    fn live_of(env: Env, of: Address) -> Option<Address>;
+    // live delegate of `of` at the end of `at_block`, or `of` itself if it had none.
+    // Lets the dao follow the chain of delegates that `power_at` was computed with.
+    fn dlg_at(env: Env, of: Address, at_block: u32) -> Address;
+    // longest chain of live delegations, counted in delegations.
+    // Only checked when delegating, so lowering it keeps the existing chains.
+    fn set_depth(env: Env, admin: Address, depth: u32);
+    fn max_depth(env: Env) -> u32;
+    // move the power history of `of` to the current storage layout.
+    // Anyone can call it, it also happens on the next power change of `of`.
+    fn migrate_cp(env: Env, of: Address);
+    // make holders without a live delegate their own live delegate.
+    // Can only be changed before any tokens are minted.
+    fn set_auto(env: Env, admin: Address, enabled: bool);
+    fn auto_power(env: Env) -> bool;
 }
 
 struct DaoExtension;
@@ -67,27 +267,174 @@ struct DaoExtension;
 #[contractimpl]
 impl DaoExtensionTrait for DaoExtension {
     fn power(env: Env, of: Address) -> i128 {
-        return get_power(&env, of);
+        return get_power(&env, of.clone()) + get_timed_power_at(&env, of, env.ledger().sequence());
     }
 
     fn power_at(env: Env, of: Address, at_block: u32) -> i128 {
-        get_power_at_or_before(&env, of, at_block)
+        get_power_at_or_before(&env, of.clone(), at_block) + get_timed_power_at(&env, of, at_block)
+    }
+
+    fn power_at_t(env: Env, of: Address, at_time: u64) -> i128 {
+        if at_time >= env.ledger().timestamp() {
+            return Self::power(env, of);
+        }
+        let after = get_block_after_time(&env, of.clone(), at_time)
+            .unwrap_or_else(|| env.ledger().sequence());
+        match after.checked_sub(1) {
+            Some(block) => Self::power_at(env, of, block),
+            None => 0,
+        }
     }
 
     fn delegate(env: Env, from: Address, to: Address, amount: i128) {
         from.require_auth();
 
-        add_delgation(&env, from, to, amount)
+        add_delgation(&env, from.clone(), to.clone(), amount);
+        event::delegate(&env, from, to, amount)
     }
 
     fn r_delegate(env: Env, from: Address, to: Address, amount: i128) {
         from.require_auth();
-        remove_delegation(&env, from, to, amount)
+        remove_delegation(&env, from.clone(), to.clone(), amount);
+        event::r_delegate(&env, from, to, amount)
+    }
+
+    fn redelegate(env: Env, from: Address, old_to: Address, new_to: Address, amount: i128) {
+        from.require_auth();
+        move_delegation(&env, from.clone(), old_to.clone(), new_to.clone(), amount);
+        event::r_delegate(&env, from.clone(), old_to, amount);
+        event::delegate(&env, from, new_to, amount)
+    }
+
+    fn r_dlg_all(env: Env, from: Address) {
+        from.require_auth();
+        remove_all_delegations(&env, from)
     }
 
     fn get_d_a(env: Env, from: Address, to: Address) -> i128 {
         get_delagate_amount_from_to(&env, from, to)
     }
+
+    fn dlgs_from(env: Env, from: Address, start: u32, limit: u32) -> Vec<Delegation> {
+        let mut delegations = Vec::new(&env);
+        for to in get_edges(&env, EdgeList::Out(from.clone()), start, limit).iter() {
+            let to = to.unwrap_optimized();
+            let amount = get_delagate_amount_from_to(&env, from.clone(), to.clone());
+            delegations.push_back(Delegation {
+                from: from.clone(),
+                to,
+                amount,
+            });
+        }
+        delegations
+    }
+
+    fn dlgtors_of(env: Env, to: Address, start: u32, limit: u32) -> Vec<Delegation> {
+        let mut delegations = Vec::new(&env);
+        for from in get_edges(&env, EdgeList::In(to.clone()), start, limit).iter() {
+            let from = from.unwrap_optimized();
+            let amount = get_delagate_amount_from_to(&env, from.clone(), to.clone());
+            delegations.push_back(Delegation {
+                from,
+                to: to.clone(),
+                amount,
+            });
+        }
+        delegations
+    }
+
+    fn timed_from(env: Env, from: Address, start: u32, limit: u32) -> Vec<TimedDelegation> {
+        let mut delegations = Vec::new(&env);
+        for to in get_edges(&env, EdgeList::TimedOut(from.clone()), start, limit).iter() {
+            delegations.push_back(get_timed_delegation(
+                &env,
+                from.clone(),
+                to.unwrap_optimized(),
+            ));
+        }
+        delegations
+    }
+
+    fn timed_to(env: Env, to: Address, start: u32, limit: u32) -> Vec<TimedDelegation> {
+        let mut delegations = Vec::new(&env);
+        for from in get_edges(&env, EdgeList::TimedIn(to.clone()), start, limit).iter() {
+            delegations.push_back(get_timed_delegation(
+                &env,
+                from.unwrap_optimized(),
+                to.clone(),
+            ));
+        }
+        delegations
+    }
+
+    fn dlg_until(env: Env, from: Address, to: Address, amount: i128, expiry: u32) {
+        from.require_auth();
+        add_timed_delegation(&env, from.clone(), to.clone(), amount, expiry);
+        event::dlg_until(&env, from, to, amount, expiry)
+    }
+
+    fn reclaim(env: Env, from: Address, to: Address) {
+        from.require_auth();
+        let amount = remove_timed_delegation(&env, from.clone(), to.clone());
+        event::reclaim(&env, from, to, amount)
+    }
+
+    fn timed_at(env: Env, of: Address, at_block: u32) -> i128 {
+        get_timed_power_at(&env, of, at_block)
+    }
+
+    fn live_at(env: Env, of: Address, at_block: u32) -> i128 {
+        get_checkpoint_at_or_before(&env, of, at_block).map_or(0, |checkpoint| checkpoint.live)
+    }
+
+    fn live_dlg(env: Env, from: Address, to: Address) {
+        from.require_auth();
+        set_live_delegation(&env, from.clone(), to.clone());
+        event::live_dlg(&env, from, to)
+    }
+
+    fn r_live_dlg(env: Env, from: Address) {
+        from.require_auth();
+        remove_live_delegation(&env, from.clone());
+        event::r_live_dlg(&env, from)
+    }
+
+    fn live_of(env: Env, of: Address) -> Option<Address> {
+        get_live_delegate(&env, of)
+    }
+
+    fn set_auto(env: Env, admin: Address, enabled: bool) {
+        check_admin(&env, &admin);
+        admin.require_auth();
+
+        // existing balances would have no power otherwise
+        if read_supply(&env) != 0 {
+            panic_with_error!(&env, DaoError::SupplyNotZero)
+        }
+        env.storage().set(&DaoDataKey::AutoSelf, &enabled)
+    }
+
+    fn auto_power(env: Env) -> bool {
+        get_auto_power(&env)
+    }
+
+    fn dlg_at(env: Env, of: Address, at_block: u32) -> Address {
+        get_upstream_at_or_before(&env, of, at_block)
+    }
+
+    fn set_depth(env: Env, admin: Address, depth: u32) {
+        check_admin(&env, &admin);
+        admin.require_auth();
+        env.storage().set(&DaoDataKey::MaxDepth, &depth)
+    }
+
+    fn max_depth(env: Env) -> u32 {
+        get_max_depth(&env)
+    }
+
+    fn migrate_cp(env: Env, of: Address) {
+        migrate_checkpoints(&env, of)
+    }
 }
 
 fn get_power(env: &Env, of: Address) -> i128 {
@@ -101,125 +448,553 @@ fn set_power(env: &Env, of: Address, power: i128) {
     env.storage().set(&DaoDataKey::Power(of), &power)
 }
 
-fn add_power(env: &Env, of: Address, amount: i128) {
-    if amount < 0 {
-        panic_with_error!(env, DaoError::CannotAddNegativePower);
-    }
+// `live` -> the part of `amount` that flows in from live delegators
+fn update_power(env: &Env, of: Address, amount: i128, live: i128) {
     let power = get_power(env, of.clone());
     let new_power = power + amount;
 
     set_power(env, of.clone(), new_power);
-    add_power_change(env, of.clone(), new_power);
+    write_checkpoint(env, of, |checkpoint| {
+        checkpoint.power = new_power;
+        checkpoint.live += live;
+    })
 }
 
-fn remove_power(env: &Env, of: Address, amount: i128) {
+fn add_delgation(env: &Env, from: Address, to: Address, amount: i128) {
     if amount < 0 {
-        panic_with_error!(env, DaoError::CannotRemoveNegativePower);
+        panic_with_error!(env, DaoError::CannotDelegateNegative)
     }
-    let power = get_power(env, of.clone());
-    let new_power = power - amount;
+    let current_delegate_amount = get_delagate_amount_from_to(env, from.clone(), to.clone());
 
-    set_power(env, of.clone(), new_power);
-    add_power_change(env, of.clone(), new_power)
+    // remove_power(env, from.clone(), amount);
+    change_power(env, to.clone(), amount);
+
+    update_delegated_amount(
+        env,
+        from.clone(),
+        to.clone(),
+        amount + current_delegate_amount,
+    );
+    spend_balance(env, from.clone(), amount);
 }
 
-// store that the power changed at this block
-fn add_power_change(env: &Env, of: Address, power: i128) {
-    let mut current_changes = get_power_changes(env, of.clone());
+// `from` -> the person that delegated originally and wants to remove their delegation.
+// `amount` -> amount that we want to remove. Should be positive and not negative.
+fn remove_delegation(env: &Env, from: Address, to: Address, amount: i128) {
+    if amount < 0 {
+        panic_with_error!(env, DaoError::CannotDelegateNegative)
+    }
+    let current_delegate_amount = get_delagate_amount_from_to(env, from.clone(), to.clone());
 
-    // Todo: is this actually necesary?
-    // check whether we haven't allready added this sequence to the vec. Might happen if two transaction in the same block calls this fun?
-    // so don't add a duplicate sequence nr.
-    if !(current_changes.len() > 0
-        && current_changes.last().unwrap_optimized().unwrap_optimized() == env.ledger().sequence())
-    {
-        current_changes.push_back(env.ledger().sequence())
+    // if we want to remove more than is delegated
+    if amount > current_delegate_amount {
+        panic_with_error!(env, DaoError::NotEnoughToken)
     }
 
+    // the tokens go back to the balance, which counts for `from` if it has a live delegate
+    change_power(env, to.clone(), -amount);
+
+    update_delegated_amount(
+        env,
+        from.clone(),
+        to.clone(),
+        current_delegate_amount - amount,
+    );
+    receive_balance(env, from.clone(), amount);
+}
+
+// the tokens stay locked, so only the power of the two delegates changes
+fn move_delegation(env: &Env, from: Address, old_to: Address, new_to: Address, amount: i128) {
+    if amount < 0 {
+        panic_with_error!(env, DaoError::CannotDelegateNegative)
+    }
+    let old_amount = get_delagate_amount_from_to(env, from.clone(), old_to.clone());
+    if amount > old_amount {
+        panic_with_error!(env, DaoError::NotEnoughToken)
+    }
+
+    let mut changes = Map::new(env);
+    collect_power_change(env, &mut changes, old_to.clone(), -amount);
+    collect_power_change(env, &mut changes, new_to.clone(), amount);
+    apply_power_changes(env, changes);
+
+    update_delegated_amount(env, from.clone(), old_to, old_amount - amount);
+    let new_amount = get_delagate_amount_from_to(env, from.clone(), new_to.clone());
+    update_delegated_amount(env, from, new_to, new_amount + amount);
+}
+
+fn remove_all_delegations(env: &Env, from: Address) {
+    let mut changes = Map::new(env);
+    let mut total = 0;
+    let list = EdgeList::Out(from.clone());
+    for to in get_edges(env, list.clone(), 0, get_edge_count(env, list)).iter() {
+        let to = to.unwrap_optimized();
+        let amount = get_delagate_amount_from_to(env, from.clone(), to.clone());
+        collect_power_change(env, &mut changes, to.clone(), -amount);
+        update_delegated_amount(env, from.clone(), to.clone(), 0);
+        event::r_delegate(env, from.clone(), to, amount);
+        total += amount;
+    }
+
+    // what `receive_balance` would do, in the same checkpoints
+    if get_live_delegate(env, from.clone()).is_some() {
+        collect_power_change(env, &mut changes, from.clone(), total);
+    }
+    apply_power_changes(env, changes);
+    add_to_balance(env, from, total);
+}
+
+// stores the amount, and keeps the lists of delegations in sync
+fn update_delegated_amount(env: &Env, from: Address, to: Address, amount: i128) {
+    let current = get_delagate_amount_from_to(env, from.clone(), to.clone());
+    if current == 0 && amount > 0 {
+        add_edge(env, EdgeList::Out(from.clone()), to.clone());
+        add_edge(env, EdgeList::In(to.clone()), from.clone());
+    } else if current > 0 && amount == 0 {
+        remove_edge(env, EdgeList::Out(from.clone()), to.clone());
+        remove_edge(env, EdgeList::In(to.clone()), from.clone());
+    }
+    set_delgate_amount_from_to(env, from, to, amount)
+}
+
+fn get_edge_count(env: &Env, list: EdgeList) -> u32 {
+    env.storage()
+        .get(&DaoDataKey::EdgeCount(list))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+fn get_edge(env: &Env, list: EdgeList, index: u32) -> Address {
     env.storage()
-        .set(&DaoDataKey::PChanges(of.clone()), &current_changes);
+        .get(&DaoDataKey::Edge(EdgeKey { list, index }))
+        .unwrap_optimized()
+        .unwrap_optimized()
+}
 
+fn set_edge(env: &Env, list: EdgeList, index: u32, other: Address) {
     env.storage().set(
-        &DaoDataKey::PowerAt(PowerAtArgs {
-            block: env.ledger().sequence(),
-            ident: of.clone(),
+        &DaoDataKey::Edge(EdgeKey {
+            list: list.clone(),
+            index,
         }),
-        &power,
-    )
+        &other,
+    );
+    env.storage()
+        .set(&DaoDataKey::EdgeIndex(EdgeOf { list, other }), &index)
+}
+
+// addresses at the other end of the delegations in `list`, at most `limit` starting at
+// index `start`. Only reads the entries it returns.
+fn get_edges(env: &Env, list: EdgeList, start: u32, limit: u32) -> Vec<Address> {
+    let end = get_edge_count(env, list.clone()).min(start.saturating_add(limit));
+    let mut edges = Vec::new(env);
+    for index in start..end {
+        edges.push_back(get_edge(env, list.clone(), index));
+    }
+    edges
 }
 
-// get the blocks at which this identifier's power changed
-fn get_power_changes(env: &Env, of: Address) -> Vec<u32> {
+fn add_edge(env: &Env, list: EdgeList, other: Address) {
+    let count = get_edge_count(env, list.clone());
+    set_edge(env, list.clone(), count, other);
     env.storage()
-        .get(&DaoDataKey::PChanges(of))
-        .unwrap_or(Ok(Vec::new(env)))
+        .set(&DaoDataKey::EdgeCount(list), &(count + 1))
+}
+
+// the last delegation takes the place of the removed one, so this costs the same
+// however long the list is
+fn remove_edge(env: &Env, list: EdgeList, other: Address) {
+    let key = DaoDataKey::EdgeIndex(EdgeOf {
+        list: list.clone(),
+        other,
+    });
+    let index: u32 = match env.storage().get(&key) {
+        Some(index) => index.unwrap_optimized(),
+        None => return,
+    };
+    env.storage().remove(&key);
+
+    let last = get_edge_count(env, list.clone()) - 1;
+    if index != last {
+        let moved = get_edge(env, list.clone(), last);
+        set_edge(env, list.clone(), index, moved);
+    }
+    env.storage().remove(&DaoDataKey::Edge(EdgeKey {
+        list: list.clone(),
+        index: last,
+    }));
+    env.storage().set(&DaoDataKey::EdgeCount(list), &last)
+}
+
+fn get_auto_power(env: &Env) -> bool {
+    env.storage()
+        .get(&DaoDataKey::AutoSelf)
+        .unwrap_or(Ok(false))
         .unwrap_optimized()
 }
 
-fn get_power_at_or_before(env: &Env, of: Address, at_or_before: u32) -> i128 {
-    let changes = get_power_changes(env, of.clone());
-    if changes.len() == 0 {
-        return 0;
+// in auto power mode, holders without a live delegate are their own delegate
+fn get_live_delegate(env: &Env, of: Address) -> Option<Address> {
+    match env.storage().get(&DaoDataKey::LiveDlg(of.clone())) {
+        Some(to) => Some(to.unwrap_optimized()),
+        None if get_auto_power(env) => Some(of),
+        None => None,
     }
-    let res = changes.binary_search(at_or_before);
+}
+
+// the live delegate that the power of `of` flows on to
+fn get_upstream(env: &Env, of: Address) -> Option<Address> {
+    get_live_delegate(env, of.clone()).filter(|to| *to != of)
+}
+
+fn get_max_depth(env: &Env) -> u32 {
+    env.storage()
+        .get(&DaoDataKey::MaxDepth)
+        .unwrap_or(Ok(DEFAULT_MAX_DEPTH))
+        .unwrap_optimized()
+}
+
+// add `amount` to the power of `of` and of every delegate up the chain.
+// `amount` is negative to remove power.
+fn change_power(env: &Env, of: Address, amount: i128) {
+    let mut changes = Map::new(env);
+    collect_power_change(env, &mut changes, of, amount);
+    apply_power_changes(env, changes)
+}
 
-    let latest_seq_at_or_before = if let Ok(_) = res {
-        // `at_or_before` sequence is in list
-        at_or_before
+// sums up the changes of one call, so that every address gets a single checkpoint
+fn collect_power_change(env: &Env, changes: &mut PowerChanges, of: Address, amount: i128) {
+    add_change(changes, of.clone(), amount);
+    collect_upstream_change(env, changes, of, amount)
+}
+
+// the change flows in from a live delegator for the delegates above `of`
+fn collect_upstream_change(env: &Env, changes: &mut PowerChanges, of: Address, amount: i128) {
+    let mut of = of;
+    while let Some(up) = get_upstream(env, of) {
+        let (power, live) = changes
+            .get(up.clone())
+            .unwrap_or(Ok((0, 0)))
+            .unwrap_optimized();
+        changes.set(up.clone(), (power + amount, live + amount));
+        of = up;
+    }
+}
+
+// change the power of `of` only, without the delegates above it
+fn add_change(changes: &mut PowerChanges, of: Address, amount: i128) {
+    let (power, live) = changes
+        .get(of.clone())
+        .unwrap_or(Ok((0, 0)))
+        .unwrap_optimized();
+    changes.set(of, (power + amount, live));
+}
+
+fn apply_power_changes(env: &Env, changes: PowerChanges) {
+    for change in changes.iter() {
+        let (of, (amount, live)) = change.unwrap_optimized();
+        if amount != 0 || live != 0 {
+            update_power(env, of, amount, live)
+        }
+    }
+}
+
+fn set_live_delegation(env: &Env, from: Address, to: Address) {
+    replace_live_delegation(env, from, Some(to))
+}
+
+// in auto power mode the power goes back to `from`
+fn remove_live_delegation(env: &Env, from: Address) {
+    replace_live_delegation(env, from, None)
+}
+
+// `from` keeps its power, which now includes its balance if it has a live delegate,
+// and all of it flows on to the new upstream instead of the old one
+fn replace_live_delegation(env: &Env, from: Address, to: Option<Address>) {
+    if let Some(to) = to.clone().filter(|to| *to != from) {
+        check_chain(env, from.clone(), to);
+    }
+
+    let balance = read_balance(env, from.clone());
+    let mut power = get_power(env, from.clone());
+    let mut changes = Map::new(env);
+    if let Some(old) = get_live_delegate(env, from.clone()) {
+        if old != from {
+            collect_upstream_change(env, &mut changes, from.clone(), -power);
+            remove_delegator(env, old, from.clone());
+        }
+        add_change(&mut changes, from.clone(), -balance);
+        power -= balance;
+    }
+
+    match to {
+        Some(to) => env.storage().set(&DaoDataKey::LiveDlg(from.clone()), &to),
+        None => env.storage().remove(&DaoDataKey::LiveDlg(from.clone())),
+    }
+
+    let new = get_live_delegate(env, from.clone());
+    if let Some(new) = new.clone() {
+        add_change(&mut changes, from.clone(), balance);
+        power += balance;
+        if new != from {
+            collect_upstream_change(env, &mut changes, from.clone(), power);
+            add_delegator(env, new, from.clone());
+        }
+    }
+    apply_power_changes(env, changes);
+    add_upstream_change(env, from.clone(), new.unwrap_or(from));
+}
+
+// `from` can't delegate to an address it is upstream of, and the longest chain through
+// the new delegation can't be longer than the max depth
+fn check_chain(env: &Env, from: Address, to: Address) {
+    // delegations below `from`, this one, and the ones above `to`
+    let mut depth = get_height(env, from.clone()) + 1;
+    let mut up = Some(to);
+    while let Some(of) = up {
+        if of == from {
+            panic_with_error!(env, DaoError::DelegationCycle)
+        }
+        up = get_upstream(env, of);
+        if up.is_some() {
+            depth += 1;
+        }
+    }
+
+    if depth > get_max_depth(env) {
+        panic_with_error!(env, DaoError::ChainTooDeep)
+    }
+}
+
+fn get_height(env: &Env, of: Address) -> u32 {
+    env.storage()
+        .get(&DaoDataKey::Height(of))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+fn set_height(env: &Env, of: Address, height: u32) {
+    env.storage().set(&DaoDataKey::Height(of), &height)
+}
+
+fn get_height_count(env: &Env, of: Address, height: u32) -> u32 {
+    env.storage()
+        .get(&DaoDataKey::HCount(HeightKey { of, height }))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+fn add_height_count(env: &Env, of: Address, height: u32, amount: i32) {
+    let count = get_height_count(env, of.clone(), height) as i32 + amount;
+    let key = DaoDataKey::HCount(HeightKey { of, height });
+    if count == 0 {
+        env.storage().remove(&key)
     } else {
-        // index is the index where the new element should be inserted in the vec, so that it stays sorted.
-        // This mean that to actually get the value we want we neec to do vec[index-1]
-        let index = unsafe { res.unwrap_err_unchecked() };
-        if index == 0 {
-            return 0;
+        env.storage().set(&key, &(count as u32))
+    }
+}
+
+fn add_delegator(env: &Env, to: Address, from: Address) {
+    let height = get_height(env, from);
+    move_delegator_height(env, to, None, Some(height))
+}
+
+fn remove_delegator(env: &Env, to: Address, from: Address) {
+    let height = get_height(env, from);
+    move_delegator_height(env, to, Some(height), None)
+}
+
+// a live delegator of `of` moved from height `old` to `new`, `None` if it was added or removed.
+// The height of `of` is one more than the highest of its delegators, so it only has to look
+// further down when the last delegator at the highest one left. The delegates above follow.
+fn move_delegator_height(env: &Env, of: Address, old: Option<u32>, new: Option<u32>) {
+    let (mut of, mut old, mut new) = (of, old, new);
+    loop {
+        if let Some(old) = old {
+            add_height_count(env, of.clone(), old, -1);
         }
-        changes.get(index - 1).unwrap_optimized().unwrap_optimized()
-    };
+        if let Some(new) = new {
+            add_height_count(env, of.clone(), new, 1);
+        }
+
+        let height = get_height(env, of.clone());
+        let new_height = match (old, new) {
+            (_, Some(new)) if new + 1 > height => new + 1,
+            (Some(old), _) if old + 1 == height && get_height_count(env, of.clone(), old) == 0 => {
+                (0..old)
+                    .rev()
+                    .find(|below| get_height_count(env, of.clone(), *below) > 0)
+                    .map_or(0, |below| below + 1)
+            }
+            _ => height,
+        };
+        if new_height == height {
+            break;
+        }
+        set_height(env, of.clone(), new_height);
+
+        match get_upstream(env, of) {
+            Some(up) => {
+                (of, old, new) = (up, Some(height), Some(new_height));
+            }
+            None => break,
+        }
+    }
+}
+
+fn get_upstream_count(env: &Env, of: Address) -> u32 {
+    env.storage()
+        .get(&DaoDataKey::DlgCount(of))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
 
+fn get_upstream_change(env: &Env, ident: Address, index: u32) -> DelegateAt {
     env.storage()
-        .get(&DaoDataKey::PowerAt(PowerAtArgs {
-            block: latest_seq_at_or_before,
-            ident: of.clone(),
-        }))
+        .get(&DaoDataKey::DlgAt(CheckpointKey { ident, index }))
         .unwrap_optimized()
         .unwrap_optimized()
 }
 
-fn add_delgation(env: &Env, from: Address, to: Address, amount: i128) {
-    let current_delegate_amount = get_delagate_amount_from_to(env, from.clone(), to.clone());
+// store the live delegate of `of` at this block, `of` itself if it has none.
+// Indexed like the power checkpoints, so the history is never read as a whole.
+fn add_upstream_change(env: &Env, of: Address, delegate: Address) {
+    let block = env.ledger().sequence();
+    let count = get_upstream_count(env, of.clone());
+    // only one delegate per block, like the power
+    let index = if count > 0 && get_upstream_change(env, of.clone(), count - 1).block == block {
+        count - 1
+    } else {
+        env.storage()
+            .set(&DaoDataKey::DlgCount(of.clone()), &(count + 1));
+        count
+    };
+    env.storage().set(
+        &DaoDataKey::DlgAt(CheckpointKey { ident: of, index }),
+        &DelegateAt { block, delegate },
+    )
+}
 
-    // remove_power(env, from.clone(), amount);
-    add_power(env, to.clone(), amount);
+// reads one change per step of the binary search
+fn get_upstream_at_or_before(env: &Env, of: Address, at_or_before: u32) -> Address {
+    let count = get_upstream_count(env, of.clone());
+    match search(count, |index| {
+        get_upstream_change(env, of.clone(), index).block <= at_or_before
+    }) {
+        Some(index) => get_upstream_change(env, of, index).delegate,
+        None => of,
+    }
+}
 
-    set_delgate_amount_from_to(
-        env,
-        from.clone(),
-        to.clone(),
-        amount + current_delegate_amount,
-    );
-    spend_balance(env, from.clone(), amount);
+// called on every balance change. The balance of an address with a live delegate counts
+// for its own power, which flows on to the delegate.
+// Tokens locked by `delegate` leave the balance, so they stop counting for the live delegate.
+pub fn move_live_power(env: &Env, of: Address, amount: i128) {
+    if get_live_delegate(env, of.clone()).is_some() {
+        change_power(env, of, amount)
+    }
 }
 
-// `from` -> the person that delegated originally and wants to remove their delegation.
-// `amount` -> amount that we want to remove. Should be positive and not negative.
-fn remove_delegation(env: &Env, from: Address, to: Address, amount: i128) {
-    let current_delegate_amount = get_delagate_amount_from_to(env, from.clone(), to.clone());
+// store a change of the checkpointed values of `of` at this block, and publish how it changes
+// what `power` returns. The timed delegations that ended since the last checkpoint are
+// published before, as nothing is written when they end.
+fn write_checkpoint(env: &Env, of: Address, update: impl FnOnce(&mut Checkpoint)) {
+    let now = env.ledger().sequence();
+    let last = get_checkpoint_at_or_before(env, of.clone(), now).unwrap_or_default();
+    let total = |checkpoint: &Checkpoint, at_block| {
+        checkpoint.power + get_timed_power(env, of.clone(), checkpoint, at_block)
+    };
+    let (published, old) = (total(&last, last.block), total(&last, now));
 
-    // if we want to remove more than is delegated
-    if amount > current_delegate_amount {
-        panic_with_error!(env, DaoError::NotEnoughToken)
+    let new = total(&add_checkpoint(env, of.clone(), update), now);
+    if old != published {
+        event::power_chg(env, of.clone(), published, old)
+    }
+    if new != old {
+        event::power_chg(env, of, old, new)
     }
+}
 
-    remove_power(env, to.clone(), amount);
-    add_power(env, from.clone(), amount);
+// what was delegated with `dlg_until` up to `at_block`, less what ended by then
+fn get_timed_power_at(env: &Env, of: Address, at_block: u32) -> i128 {
+    match get_checkpoint_at_or_before(env, of.clone(), at_block) {
+        Some(checkpoint) => get_timed_power(env, of, &checkpoint, at_block),
+        None => 0,
+    }
+}
 
-    set_delgate_amount_from_to(
-        env,
-        from.clone(),
-        to.clone(),
-        current_delegate_amount - amount,
-    );
-    receive_balance(env, from.clone(), amount);
+// the timed power at `at_block` of the last `checkpoint` at or before it
+fn get_timed_power(env: &Env, of: Address, checkpoint: &Checkpoint, at_block: u32) -> i128 {
+    // most addresses never get any, which saves reading the tree
+    if checkpoint.timed == 0 {
+        return 0;
+    }
+    checkpoint.timed - get_expired(env, of, at_block)
+}
+
+fn get_timed_delegation(env: &Env, from: Address, to: Address) -> TimedDelegation {
+    env.storage()
+        .get(&DaoDataKey::TimedDlg(DelegateAmountArgs { from, to }))
+        .unwrap_or_else(|| panic_with_error!(env, DaoError::NoDelegation))
+        .unwrap_optimized()
+}
+
+fn add_timed_delegation(env: &Env, from: Address, to: Address, amount: i128, expiry: u32) {
+    if amount <= 0 {
+        panic_with_error!(env, DaoError::AmountNotPositive)
+    }
+    if expiry <= env.ledger().sequence() {
+        panic_with_error!(env, DaoError::ExpiryInPast)
+    }
+    let key = DaoDataKey::TimedDlg(DelegateAmountArgs {
+        from: from.clone(),
+        to: to.clone(),
+    });
+    if env.storage().has(&key) {
+        panic_with_error!(env, DaoError::DelegationExists)
+    }
+
+    spend_balance(env, from.clone(), amount);
+
+    let delegation = TimedDelegation {
+        from: from.clone(),
+        to: to.clone(),
+        amount,
+        start: env.ledger().sequence(),
+        end: expiry,
+    };
+    env.storage().set(&key, &delegation);
+    add_edge(env, EdgeList::TimedOut(from.clone()), to.clone());
+    add_edge(env, EdgeList::TimedIn(to.clone()), from);
+
+    write_checkpoint(env, to.clone(), |checkpoint| {
+        add_expiry(env, to, expiry, amount);
+        checkpoint.timed += amount
+    })
+}
+
+// gives the tokens back, and ends the power early if it didn't expire yet
+fn remove_timed_delegation(env: &Env, from: Address, to: Address) -> i128 {
+    let key = DaoDataKey::TimedDlg(DelegateAmountArgs {
+        from: from.clone(),
+        to: to.clone(),
+    });
+    let delegation = get_timed_delegation(env, from.clone(), to.clone());
+    env.storage().remove(&key);
+    remove_edge(env, EdgeList::TimedOut(from.clone()), to.clone());
+    remove_edge(env, EdgeList::TimedIn(to.clone()), from.clone());
+
+    // it ends now instead
+    let now = env.ledger().sequence();
+    if delegation.end > now {
+        write_checkpoint(env, to.clone(), |_| {
+            add_expiry(env, to.clone(), delegation.end, -delegation.amount);
+            add_expiry(env, to, now, delegation.amount);
+        });
+    }
+
+    receive_balance(env, from, delegation.amount);
+    delegation.amount
 }
 
 fn get_delagate_amount_from_to(env: &Env, from: Address, to: Address) -> i128 {
@@ -241,9 +1016,17 @@ mod dao_test {
     extern crate std;
     use std::println;
 
-    use crate::contract::TokenClient;
-    use crate::dao::{DaoExtension, DaoExtensionClient};
-    use soroban_sdk::{unwrap::UnwrapOptimized, vec, Bytes, Env};
+    use crate::checkpoint::{add_checkpoint, get_checkpoint_count, get_power_at_or_before};
+    use crate::contract::{Token, TokenClient};
+    use crate::dao::{
+        DaoDataKey, DaoError, DaoExtension, DaoExtensionClient, Delegation, PowerAtArgs,
+        TimedDelegation, TokenError,
+    };
+    use soroban_sdk::testutils::budget::CostType;
+    use soroban_sdk::testutils::{Address as _, ContractFunctionSet, Events, Ledger, LedgerInfo};
+    use soroban_sdk::{
+        symbol, unwrap::UnwrapOptimized, vec, Address, Bytes, Env, IntoVal, RawVal, Symbol,
+    };
 
     #[test]
     fn test() {
@@ -272,4 +1055,839 @@ mod dao_test {
         // //     .with_source_account(&user_2)
         // //     .delegate(&soroban_auth::Signature::Invoker, &0, &user_2.clone().into(), &10);
     }
+
+    // serves the token and the dao extension functions from one contract, like the wasm does
+    struct TokenWithDao;
+
+    impl ContractFunctionSet for TokenWithDao {
+        fn call(&self, func: &Symbol, env: Env, args: &[RawVal]) -> Option<RawVal> {
+            Token
+                .call(func, env.clone(), args)
+                .or_else(|| DaoExtension.call(func, env, args))
+        }
+    }
+
+    fn create_token(env: &Env, admin: &Address) -> (TokenClient, DaoExtensionClient) {
+        let id = env.register_contract(None, TokenWithDao);
+        let token = TokenClient::new(env, &id);
+        token.initialize(admin, &7, &"name".into_val(env), &"symbol".into_val(env));
+        (token, DaoExtensionClient::new(env, &id))
+    }
+
+    fn set_sequence(env: &Env, sequence_number: u32) {
+        set_ledger(env, sequence_number, 0)
+    }
+
+    fn set_ledger(env: &Env, sequence_number: u32, timestamp: u64) {
+        env.ledger().set(LedgerInfo {
+            timestamp,
+            protocol_version: 1,
+            sequence_number,
+            network_id: Default::default(),
+            base_reserve: 10,
+        });
+    }
+
+    #[test]
+    fn test_live_delegation() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let holder = Address::random(&env);
+        let other = Address::random(&env);
+        let delegate_1 = Address::random(&env);
+        let delegate_2 = Address::random(&env);
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+
+        token.mint(&admin, &holder, &100);
+        dao.live_dlg(&holder, &delegate_1);
+        assert_eq!(Some(delegate_1.clone()), dao.live_of(&holder));
+        assert_eq!(100, dao.power(&delegate_1));
+
+        // the power follows the balance, which stays spendable
+        set_sequence(&env, 2);
+        token.xfer(&holder, &other, &30);
+        token.mint(&admin, &holder, &50);
+        token.burn(&holder, &20);
+        token.clawback(&admin, &holder, &10);
+        token.incr_allow(&holder, &other, &10);
+        token.xfer_from(&other, &holder, &other, &10);
+        assert_eq!(80, token.balance(&holder));
+        assert_eq!(80, dao.power(&delegate_1));
+        assert_eq!(100, dao.power_at(&delegate_1, &1));
+
+        // switching moves all of it
+        set_sequence(&env, 3);
+        dao.live_dlg(&holder, &delegate_2);
+        assert_eq!(0, dao.power(&delegate_1));
+        assert_eq!(80, dao.power(&delegate_2));
+        // the balance also counts for the holder, so it can still vote with it itself
+        assert_eq!(80, dao.power(&holder));
+
+        // locked delegations leave the balance, so they don't count twice
+        dao.delegate(&holder, &delegate_1, &30);
+        assert_eq!(30, dao.power(&delegate_1));
+        assert_eq!(50, dao.power(&delegate_2));
+        dao.r_delegate(&holder, &delegate_1, &30);
+        assert_eq!(80, dao.power(&delegate_2));
+
+        set_sequence(&env, 4);
+        dao.r_live_dlg(&holder);
+        assert_eq!(None, dao.live_of(&holder));
+        assert_eq!(0, dao.power(&delegate_2));
+        assert_eq!(80, dao.power_at(&delegate_2, &3));
+
+        // the live delegates are kept for each ledger, the holder itself when it had none
+        assert_eq!(
+            [
+                holder.clone(),
+                delegate_1.clone(),
+                delegate_1,
+                delegate_2,
+                holder.clone()
+            ],
+            [0, 1, 2, 3, 4].map(|block| dao.dlg_at(&holder, &block))
+        );
+    }
+
+    #[test]
+    fn test_auto_power() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let holder = Address::random(&env);
+        let other = Address::random(&env);
+        let delegate = Address::random(&env);
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+
+        assert_eq!(
+            dao.try_set_auto(&holder, &true),
+            Err(Ok(TokenError::NotAdmin.into()))
+        );
+        dao.set_auto(&admin, &true);
+        assert!(dao.auto_power());
+
+        // holders have power without delegating
+        token.mint(&admin, &holder, &100);
+        assert_eq!(100, dao.power(&holder));
+        set_sequence(&env, 2);
+        token.xfer(&holder, &other, &30);
+        assert_eq!(100, dao.power_at(&holder, &1));
+        assert_eq!(70, dao.power_at(&holder, &2));
+        assert_eq!(30, dao.power_at(&other, &2));
+
+        // the power of the holder flows on to a live delegate, and removing it stops that
+        dao.live_dlg(&holder, &delegate);
+        assert_eq!((70, 70), (dao.power(&holder), dao.power(&delegate)));
+        dao.r_live_dlg(&holder);
+        assert_eq!(Some(holder.clone()), dao.live_of(&holder));
+        assert_eq!((70, 0), (dao.power(&holder), dao.power(&delegate)));
+
+        assert_eq!(
+            dao.try_set_auto(&admin, &false),
+            Err(Ok(DaoError::SupplyNotZero.into()))
+        );
+    }
+
+    #[test]
+    fn test_delegation_chain() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate, top, other] = [0; 4].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+
+        token.mint(&admin, &holder, &100);
+        token.mint(&admin, &delegate, &50);
+        dao.live_dlg(&holder, &delegate);
+        dao.live_dlg(&delegate, &top);
+        // the balance of `delegate` counts for itself and flows on with the rest
+        assert_eq!((150, 150), (dao.power(&delegate), dao.power(&top)));
+        assert_eq!(
+            (100, 150),
+            (dao.live_at(&delegate, &1), dao.live_at(&top, &1))
+        );
+
+        // balance changes go up the whole chain
+        set_sequence(&env, 2);
+        token.xfer(&holder, &other, &10);
+        assert_eq!((140, 140), (dao.power(&delegate), dao.power(&top)));
+        assert_eq!(150, dao.power_at(&top, &1));
+        assert_eq!(top, dao.dlg_at(&delegate, &2));
+        assert_eq!(top, dao.dlg_at(&top, &2));
+
+        assert_eq!(
+            dao.try_live_dlg(&top, &holder),
+            Err(Ok(DaoError::DelegationCycle.into()))
+        );
+        dao.set_depth(&admin, &2);
+        assert_eq!(
+            dao.try_live_dlg(&other, &holder),
+            Err(Ok(DaoError::ChainTooDeep.into()))
+        );
+        assert_eq!(
+            dao.try_live_dlg(&top, &other),
+            Err(Ok(DaoError::ChainTooDeep.into()))
+        );
+
+        // leaving the chain takes the power delegated below along
+        set_sequence(&env, 3);
+        dao.live_dlg(&delegate, &other);
+        assert_eq!((0, 140), (dao.power(&top), dao.power(&other)));
+        assert_eq!(other, dao.dlg_at(&delegate, &3));
+        dao.r_live_dlg(&holder);
+        assert_eq!((50, 50), (dao.power(&delegate), dao.power(&other)));
+
+        // the chain is short enough again, and the balance of `other` counts too
+        dao.live_dlg(&other, &top);
+        assert_eq!(60, dao.power(&top));
+        assert_eq!(holder, dao.dlg_at(&holder, &3));
+    }
+
+    #[test]
+    fn test_power_at_time() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate] = [0; 2].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_ledger(&env, 1, 1000);
+        token.mint(&admin, &holder, &100);
+        dao.delegate(&holder, &delegate, &100);
+        set_ledger(&env, 2, 1005);
+        dao.r_delegate(&holder, &delegate, &40);
+        dao.dlg_until(&holder, &delegate, &10, &5);
+
+        assert_eq!(
+            (0, 100, 100, 70, 70),
+            (
+                dao.power_at_t(&delegate, &999),
+                dao.power_at_t(&delegate, &1000),
+                dao.power_at_t(&delegate, &1004),
+                dao.power_at_t(&delegate, &1005),
+                dao.power_at_t(&delegate, &u64::MAX)
+            )
+        );
+        assert_eq!(70, dao.power(&delegate));
+
+        // ledger 5, when the timed delegation ends, has no checkpoint, so later times count
+        // for it until the next one
+        set_ledger(&env, 5, 1020);
+        set_ledger(&env, 6, 1030);
+        dao.delegate(&holder, &delegate, &1);
+        set_ledger(&env, 7, 1040);
+        assert_eq!(
+            (60, 60, 61, 61),
+            (
+                dao.power_at(&delegate, &5),
+                dao.power_at_t(&delegate, &1025),
+                dao.power_at_t(&delegate, &1030),
+                dao.power_at_t(&delegate, &1040)
+            )
+        );
+    }
+
+    #[test]
+    fn test_delegation_events() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate, other] = [0; 3].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+        token.mint(&admin, &holder, &100);
+
+        let last_events = |count: u32| {
+            let events = env.events().all();
+            events.slice(events.len() - count..)
+        };
+        dao.delegate(&holder, &delegate, &100);
+        assert_eq!(
+            last_events(2),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("power_chg"), delegate.clone()).into_val(&env),
+                    (0_i128, 100_i128).into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("delegate"), holder.clone(), delegate.clone()).into_val(&env),
+                    100_i128.into_val(&env)
+                ),
+            ]
+        );
+
+        dao.r_delegate(&holder, &delegate, &40);
+        assert_eq!(
+            last_events(2),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("power_chg"), delegate.clone()).into_val(&env),
+                    (100_i128, 60_i128).into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("r_delegate"), holder.clone(), delegate.clone()).into_val(&env),
+                    40_i128.into_val(&env)
+                ),
+            ]
+        );
+
+        // moving delegations reads as taking them back and delegating again
+        dao.redelegate(&holder, &delegate, &other, &20);
+        assert_eq!(
+            last_events(2),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("r_delegate"), holder.clone(), delegate.clone()).into_val(&env),
+                    20_i128.into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("delegate"), holder.clone(), other.clone()).into_val(&env),
+                    20_i128.into_val(&env)
+                ),
+            ]
+        );
+
+        // followed by the power changes of both delegates
+        dao.r_dlg_all(&holder);
+        assert_eq!(
+            last_events(4).slice(..2),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("r_delegate"), holder.clone(), delegate.clone()).into_val(&env),
+                    40_i128.into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("r_delegate"), holder.clone(), other.clone()).into_val(&env),
+                    20_i128.into_val(&env)
+                ),
+            ]
+        );
+
+        // timed delegations count for `power` too
+        env.budget().reset();
+        dao.dlg_until(&holder, &delegate, &10, &5);
+        assert_eq!(
+            last_events(2),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("power_chg"), delegate.clone()).into_val(&env),
+                    (0_i128, 10_i128).into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("dlg_until"), holder.clone(), delegate.clone()).into_val(&env),
+                    (10_i128, 5_u32).into_val(&env)
+                ),
+            ]
+        );
+
+        dao.reclaim(&holder, &delegate);
+        assert_eq!(
+            last_events(2),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("power_chg"), delegate.clone()).into_val(&env),
+                    (10_i128, 0_i128).into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("reclaim"), holder.clone(), delegate.clone()).into_val(&env),
+                    10_i128.into_val(&env)
+                ),
+            ]
+        );
+
+        dao.live_dlg(&holder, &delegate);
+        assert_eq!(
+            last_events(1),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("live_dlg"), holder.clone()).into_val(&env),
+                    delegate.clone().into_val(&env)
+                ),
+            ]
+        );
+
+        dao.r_live_dlg(&holder);
+        assert_eq!(
+            last_events(1),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("r_live_dlg"), holder.clone()).into_val(&env),
+                    ().into_val(&env)
+                ),
+            ]
+        );
+
+        // nothing is written when a timed delegation ends, so it is published with the next change
+        env.budget().reset();
+        dao.dlg_until(&holder, &delegate, &10, &5);
+        set_sequence(&env, 6);
+        dao.delegate(&holder, &delegate, &5);
+        assert_eq!(
+            last_events(3),
+            vec![
+                &env,
+                (
+                    token.contract_id.clone(),
+                    (symbol!("power_chg"), delegate.clone()).into_val(&env),
+                    (10_i128, 0_i128).into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("power_chg"), delegate.clone()).into_val(&env),
+                    (0_i128, 5_i128).into_val(&env)
+                ),
+                (
+                    token.contract_id.clone(),
+                    (symbol!("delegate"), holder, delegate).into_val(&env),
+                    5_i128.into_val(&env)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timed_delegation() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate, top] = [0; 3].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+
+        token.mint(&admin, &holder, &100);
+        dao.dlg_until(&holder, &delegate, &40, &5);
+        assert_eq!((60, 40), (token.balance(&holder), dao.power(&delegate)));
+        assert_eq!(
+            dao.try_dlg_until(&holder, &delegate, &10, &8),
+            Err(Ok(DaoError::DelegationExists.into()))
+        );
+        assert_eq!(
+            dao.try_dlg_until(&holder, &top, &10, &1),
+            Err(Ok(DaoError::ExpiryInPast.into()))
+        );
+        assert_eq!(
+            dao.try_dlg_until(&holder, &top, &0, &8),
+            Err(Ok(DaoError::AmountNotPositive.into()))
+        );
+
+        // it stops counting without anyone calling the contract
+        assert_eq!(40, dao.power_at(&delegate, &4));
+        assert_eq!(0, dao.power_at(&delegate, &5));
+
+        set_sequence(&env, 6);
+        dao.reclaim(&holder, &delegate);
+        assert_eq!(100, token.balance(&holder));
+        assert_eq!(40, dao.timed_at(&delegate, &4));
+        assert_eq!(
+            dao.try_reclaim(&holder, &delegate),
+            Err(Ok(DaoError::NoDelegation.into()))
+        );
+
+        // it doesn't flow on to live delegates, and can be ended early
+        dao.dlg_until(&holder, &delegate, &10, &20);
+        dao.live_dlg(&delegate, &top);
+        assert_eq!((10, 0), (dao.power(&delegate), dao.power(&top)));
+        set_sequence(&env, 7);
+        dao.reclaim(&holder, &delegate);
+        assert_eq!((10, 0), (dao.power_at(&delegate, &6), dao.power(&delegate)));
+    }
+
+    #[test]
+    fn test_timed_dust() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate] = [0; 2].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+        token.mint(&admin, &holder, &100);
+
+        // delegations of others, however many, don't keep anyone from delegating
+        let holders = [0; 30].map(|_| Address::random(&env));
+        for (i, holder) in holders.iter().enumerate() {
+            env.budget().reset();
+            token.mint(&admin, holder, &1);
+            dao.dlg_until(holder, &delegate, &1, &(10 + i as u32));
+        }
+        env.budget().reset();
+        dao.dlg_until(&holder, &delegate, &10, &u32::MAX);
+        assert_eq!(40, dao.power(&delegate));
+
+        // they still count for the ledgers before they ended
+        set_sequence(&env, 20);
+        assert_eq!(
+            (40, 39, 30, 29),
+            (
+                dao.power_at(&delegate, &9),
+                dao.power_at(&delegate, &10),
+                dao.power_at(&delegate, &19),
+                dao.power(&delegate)
+            )
+        );
+        dao.reclaim(&holder, &delegate);
+        assert_eq!(
+            (30, 19, 0),
+            (
+                dao.power_at(&delegate, &19),
+                dao.power(&delegate),
+                dao.power_at(&delegate, &u32::MAX)
+            )
+        );
+    }
+
+    #[test]
+    fn test_delegation_lists() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, other, delegate_1, delegate_2] = [0; 4].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        let delegation = |from: &Address, to: &Address, amount| Delegation {
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+        };
+
+        token.mint(&admin, &holder, &100);
+        token.mint(&admin, &other, &50);
+        dao.delegate(&holder, &delegate_1, &10);
+        dao.delegate(&holder, &delegate_2, &20);
+        dao.delegate(&holder, &delegate_1, &5);
+        dao.delegate(&other, &delegate_1, &7);
+
+        assert_eq!(
+            vec![
+                &env,
+                delegation(&holder, &delegate_1, 15),
+                delegation(&holder, &delegate_2, 20)
+            ],
+            dao.dlgs_from(&holder, &0, &10)
+        );
+        assert_eq!(
+            vec![&env, delegation(&holder, &delegate_2, 20)],
+            dao.dlgs_from(&holder, &1, &1)
+        );
+        assert_eq!(0, dao.dlgs_from(&holder, &5, &3).len());
+        assert_eq!(
+            vec![
+                &env,
+                delegation(&holder, &delegate_1, 15),
+                delegation(&other, &delegate_1, 7)
+            ],
+            dao.dlgtors_of(&delegate_1, &0, &10)
+        );
+
+        // only removed once nothing is delegated anymore
+        dao.r_delegate(&holder, &delegate_1, &5);
+        assert_eq!(2, dao.dlgtors_of(&delegate_1, &0, &10).len());
+        dao.r_delegate(&holder, &delegate_1, &10);
+        assert_eq!(
+            vec![&env, delegation(&other, &delegate_1, 7)],
+            dao.dlgtors_of(&delegate_1, &0, &10)
+        );
+        assert_eq!(
+            vec![&env, delegation(&holder, &delegate_2, 20)],
+            dao.dlgs_from(&holder, &0, &10)
+        );
+    }
+
+    #[test]
+    fn test_timed_delegation_lists() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, other, delegate_1, delegate_2] = [0; 4].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+        let timed = |from: &Address, to: &Address, amount, end| TimedDelegation {
+            from: from.clone(),
+            to: to.clone(),
+            amount,
+            start: 1,
+            end,
+        };
+
+        token.mint(&admin, &holder, &100);
+        token.mint(&admin, &other, &50);
+        dao.dlg_until(&holder, &delegate_1, &10, &5);
+        dao.dlg_until(&holder, &delegate_2, &20, &8);
+        dao.dlg_until(&other, &delegate_1, &7, &8);
+
+        // expired delegations are listed until the tokens are reclaimed
+        set_sequence(&env, 6);
+        assert_eq!(
+            vec![
+                &env,
+                timed(&holder, &delegate_1, 10, 5),
+                timed(&holder, &delegate_2, 20, 8)
+            ],
+            dao.timed_from(&holder, &0, &10)
+        );
+        assert_eq!(
+            vec![&env, timed(&other, &delegate_1, 7, 8)],
+            dao.timed_to(&delegate_1, &1, &10)
+        );
+
+        // the last one takes the place of a removed one
+        dao.reclaim(&holder, &delegate_1);
+        assert_eq!(
+            vec![&env, timed(&holder, &delegate_2, 20, 8)],
+            dao.timed_from(&holder, &0, &10)
+        );
+        assert_eq!(
+            vec![&env, timed(&other, &delegate_1, 7, 8)],
+            dao.timed_to(&delegate_1, &0, &10)
+        );
+        assert_eq!(0, dao.timed_to(&delegate_1, &1, &10).len());
+    }
+
+    #[test]
+    fn test_negative_delegation() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate] = [0; 2].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+
+        token.mint(&admin, &holder, &100);
+        assert_eq!(
+            dao.try_delegate(&holder, &delegate, &-1000),
+            Err(Ok(DaoError::CannotDelegateNegative.into()))
+        );
+        dao.delegate(&holder, &delegate, &40);
+        assert_eq!(
+            dao.try_r_delegate(&holder, &delegate, &-1000),
+            Err(Ok(DaoError::CannotDelegateNegative.into()))
+        );
+        assert_eq!(
+            (60, 40, 100),
+            (token.balance(&holder), dao.power(&delegate), token.supply())
+        );
+    }
+
+    #[test]
+    fn test_redelegate() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate_1, delegate_2, top] = [0; 4].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        set_sequence(&env, 1);
+
+        token.mint(&admin, &holder, &100);
+        dao.live_dlg(&delegate_1, &top);
+        dao.live_dlg(&delegate_2, &top);
+        dao.delegate(&holder, &delegate_1, &40);
+        dao.delegate(&holder, &delegate_2, &10);
+
+        set_sequence(&env, 2);
+        dao.redelegate(&holder, &delegate_1, &delegate_2, &30);
+        assert_eq!(
+            (10, 40, 50),
+            (
+                dao.power(&delegate_1),
+                dao.power(&delegate_2),
+                dao.power(&top)
+            )
+        );
+        assert_eq!(
+            (10, 40),
+            (
+                dao.get_d_a(&holder, &delegate_1),
+                dao.get_d_a(&holder, &delegate_2)
+            )
+        );
+        assert_eq!(
+            dao.try_redelegate(&holder, &delegate_1, &delegate_2, &11),
+            Err(Ok(DaoError::NotEnoughToken.into()))
+        );
+
+        // the power of `top` didn't change, so it has no new checkpoint
+        let checkpoints = |of: &Address| {
+            env.as_contract(&token.contract_id, || {
+                get_checkpoint_count(&env, of.clone())
+            })
+        };
+        assert_eq!(1, checkpoints(&top));
+        assert_eq!(2, checkpoints(&delegate_2));
+
+        // everything goes back to the balance at once, which `top` gets again
+        dao.live_dlg(&holder, &delegate_1);
+        set_sequence(&env, 3);
+        dao.r_dlg_all(&holder);
+        assert_eq!(100, token.balance(&holder));
+        assert_eq!(
+            (100, 0, 100),
+            (
+                dao.power(&delegate_1),
+                dao.power(&delegate_2),
+                dao.power(&top)
+            )
+        );
+        assert_eq!(0, dao.dlgs_from(&holder, &0, &10).len());
+        assert_eq!(2, checkpoints(&top));
+    }
+
+    // how power changes were stored before checkpoints
+    fn legacy_power_change(env: &Env, of: &Address, power: i128) {
+        let key = DaoDataKey::PChanges(of.clone());
+        let mut changes: soroban_sdk::Vec<u32> =
+            env.storage().get(&key).unwrap_or(Ok(vec![env])).unwrap();
+        changes.push_back(env.ledger().sequence());
+        env.storage().set(&key, &changes);
+        env.storage().set(&DaoDataKey::Power(of.clone()), &power);
+        env.storage().set(
+            &DaoDataKey::PowerAt(PowerAtArgs {
+                block: env.ledger().sequence(),
+                ident: of.clone(),
+            }),
+            &power,
+        )
+    }
+
+    #[test]
+    fn test_checkpoint_migration() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [holder, delegate, other] = [0; 3].map(|_| Address::random(&env));
+        let (token, dao) = create_token(&env, &admin);
+        for block in 1..=3 {
+            set_sequence(&env, block * 10);
+            env.as_contract(&token.contract_id, || {
+                legacy_power_change(&env, &delegate, block as i128 * 100)
+            });
+        }
+        assert_eq!(
+            (100, 200),
+            (dao.power_at(&delegate, &15), dao.power_at(&delegate, &29))
+        );
+
+        // the old history keeps working until it is migrated
+        dao.migrate_cp(&delegate);
+        let has_legacy = || {
+            env.as_contract(&token.contract_id, || {
+                env.storage().has(&DaoDataKey::PChanges(delegate.clone()))
+                    || env.storage().has(&DaoDataKey::PowerAt(PowerAtArgs {
+                        block: 10,
+                        ident: delegate.clone(),
+                    }))
+            })
+        };
+        assert!(!has_legacy());
+        assert_eq!(
+            (0, 100, 300),
+            (
+                dao.power_at(&delegate, &9),
+                dao.power_at(&delegate, &15),
+                dao.power_at(&delegate, &40)
+            )
+        );
+        // it has no timestamps, so it counts for any time before the first checkpoint with one
+        set_ledger(&env, 35, 3500);
+        token.mint(&admin, &other, &5);
+        dao.delegate(&other, &delegate, &5);
+        set_ledger(&env, 36, 3600);
+        assert_eq!(
+            (300, 300, 305),
+            (
+                dao.power_at_t(&delegate, &0),
+                dao.power_at_t(&delegate, &3499),
+                dao.power_at_t(&delegate, &3500)
+            )
+        );
+
+        // or happens on the next power change
+        set_sequence(&env, 30);
+        env.as_contract(&token.contract_id, || {
+            legacy_power_change(&env, &holder, 50)
+        });
+        set_sequence(&env, 40);
+        token.mint(&admin, &holder, &10);
+        dao.live_dlg(&holder, &holder);
+        assert_eq!(
+            (50, 60),
+            (dao.power_at(&holder, &35), dao.power_at(&holder, &40))
+        );
+        assert_eq!(
+            2,
+            env.as_contract(&token.contract_id, || get_checkpoint_count(
+                &env,
+                holder.clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_budget() {
+        let env: Env = Default::default();
+        let admin = Address::random(&env);
+        let [legacy, indexed] = [0; 2].map(|_| Address::random(&env));
+        let (token, _) = create_token(&env, &admin);
+        let history = 400;
+        env.as_contract(&token.contract_id, || {
+            for block in 1..=history {
+                env.budget().reset();
+                set_sequence(&env, block);
+                legacy_power_change(&env, &legacy, block as i128);
+                add_checkpoint(&env, indexed.clone(), |checkpoint| {
+                    checkpoint.power = block as i128
+                });
+            }
+        });
+        set_sequence(&env, history + 1);
+
+        // values converted between the ledger entries and the host, which is what grows
+        // with the size of the entries read and written
+        let mut budget = env.budget();
+        let mut moved = |f: &dyn Fn()| {
+            budget.reset();
+            env.as_contract(&token.contract_id, f);
+            [
+                CostType::ValXdrConv,
+                CostType::ScVecToHostVec,
+                CostType::ScVecFromHostVec,
+                CostType::ScMapToHostMap,
+                CostType::ScMapFromHostMap,
+            ]
+            .into_iter()
+            .map(|cost| budget.input(cost))
+            .sum::<u64>()
+        };
+        let legacy_write = moved(&|| legacy_power_change(&env, &legacy, 0));
+        let indexed_write = moved(&|| {
+            add_checkpoint(&env, indexed.clone(), |checkpoint| checkpoint.power = 0);
+        });
+        let legacy_read = moved(&|| {
+            assert_eq!(50, get_power_at_or_before(&env, legacy.clone(), 50));
+        });
+        let indexed_read = moved(&|| {
+            assert_eq!(50, get_power_at_or_before(&env, indexed.clone(), 50));
+        });
+
+        std::println!(
+            "history of {}: write {} -> {}, read {} -> {}",
+            history,
+            legacy_write,
+            indexed_write,
+            legacy_read,
+            indexed_read
+        );
+        assert!(indexed_write * 5 < legacy_write);
+        assert!(indexed_read < legacy_read);
+    }
 }
@@ -39,3 +39,38 @@ pub(crate) fn burn(e: &Env, from: Address, amount: i128) {
     let topics = (symbol!("burn"), from);
     e.events().publish(topics, amount);
 }
+
+pub(crate) fn delegate(e: &Env, from: Address, to: Address, amount: i128) {
+    let topics = (symbol!("delegate"), from, to);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn r_delegate(e: &Env, from: Address, to: Address, amount: i128) {
+    let topics = (symbol!("r_delegate"), from, to);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn power_chg(e: &Env, of: Address, old_power: i128, new_power: i128) {
+    let topics = (symbol!("power_chg"), of);
+    e.events().publish(topics, (old_power, new_power));
+}
+
+pub(crate) fn dlg_until(e: &Env, from: Address, to: Address, amount: i128, expiry: u32) {
+    let topics = (symbol!("dlg_until"), from, to);
+    e.events().publish(topics, (amount, expiry));
+}
+
+pub(crate) fn reclaim(e: &Env, from: Address, to: Address, amount: i128) {
+    let topics = (symbol!("reclaim"), from, to);
+    e.events().publish(topics, amount);
+}
+
+pub(crate) fn live_dlg(e: &Env, from: Address, to: Address) {
+    let topics = (symbol!("live_dlg"), from);
+    e.events().publish(topics, to);
+}
+
+pub(crate) fn r_live_dlg(e: &Env, from: Address) {
+    let topics = (symbol!("r_live_dlg"), from);
+    e.events().publish(topics, ());
+}
@@ -0,0 +1,85 @@
+use soroban_sdk::{contracttype, unwrap::UnwrapOptimized, Address, Env};
+
+use crate::dao::DaoDataKey;
+
+// a node of the tree of the amounts that stop counting for `ident`
+#[derive(Clone)]
+#[contracttype]
+pub struct ExpiryKey {
+    pub ident: Address,
+    pub node: u64,
+}
+
+// The amounts delegated with `dlg_until` to an address are stored by the ledger they stop
+// counting at, in a Fenwick tree over the ledgers from `base` on. Ledger `base + n - 1` is node
+// `n`, and node `n` holds the sum of the `n.isolate_lowest_one()` ledgers up to it.
+// The tree grows to the last ledger that was added, so adding an amount and summing the ones up
+// to a ledger take about log2 of the ledgers it spans in storage accesses, however many
+// delegations there are.
+#[derive(Clone, Default)]
+#[contracttype]
+pub struct ExpiryTree {
+    pub base: u32,
+    pub size: u64,
+}
+
+fn get_tree(env: &Env, of: Address) -> Option<ExpiryTree> {
+    env.storage()
+        .get(&DaoDataKey::ExpTree(of))
+        .map(|tree| tree.unwrap_optimized())
+}
+
+fn get_node(env: &Env, ident: Address, node: u64) -> i128 {
+    env.storage()
+        .get(&DaoDataKey::Expiry(ExpiryKey { ident, node }))
+        .unwrap_or(Ok(0))
+        .unwrap_optimized()
+}
+
+fn set_node(env: &Env, ident: Address, node: u64, sum: i128) {
+    let key = DaoDataKey::Expiry(ExpiryKey { ident, node });
+    if sum == 0 {
+        env.storage().remove(&key)
+    } else {
+        env.storage().set(&key, &sum)
+    }
+}
+
+// `amount` stops counting for `of` from `ledger` on. Negative to move an end to another ledger.
+// `ledger` can't be before the current one.
+pub fn add_expiry(env: &Env, of: Address, ledger: u32, amount: i128) {
+    let mut tree = get_tree(env, of.clone()).unwrap_or(ExpiryTree {
+        base: env.ledger().sequence(),
+        size: 0,
+    });
+    let mut node = (ledger - tree.base) as u64 + 1;
+    if node > tree.size {
+        // the last node holds the sum of all ledgers, and so does each one it doubles to
+        let total = get_node(env, of.clone(), tree.size);
+        while node > tree.size {
+            tree.size = (tree.size * 2).max(1);
+            set_node(env, of.clone(), tree.size, total);
+        }
+        env.storage().set(&DaoDataKey::ExpTree(of.clone()), &tree);
+    }
+    while node <= tree.size {
+        let sum = get_node(env, of.clone(), node);
+        set_node(env, of.clone(), node, sum + amount);
+        node += node.isolate_lowest_one();
+    }
+}
+
+// sum of the amounts that stopped counting for `of` at or before `ledger`
+pub fn get_expired(env: &Env, of: Address, ledger: u32) -> i128 {
+    let tree = match get_tree(env, of.clone()) {
+        Some(tree) if ledger >= tree.base => tree,
+        _ => return 0,
+    };
+    let mut node = ((ledger - tree.base) as u64 + 1).min(tree.size);
+    let mut expired = 0;
+    while node > 0 {
+        expired += get_node(env, of.clone(), node);
+        node &= node - 1;
+    }
+    expired
+}
@@ -3,8 +3,10 @@
 mod admin;
 mod allowance;
 mod balance;
+mod checkpoint;
 mod contract;
 mod event;
+mod expiry;
 mod metadata;
 mod storage_types;
 
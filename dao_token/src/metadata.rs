@@ -1,9 +1,13 @@
+use crate::dao::TokenError;
 use crate::storage_types::DataKey;
-use soroban_sdk::{Bytes, Env};
+use soroban_sdk::{panic_with_error, Bytes, Env};
 
 pub fn read_decimal(e: &Env) -> u32 {
     let key = DataKey::Decimals;
-    e.storage().get_unchecked(&key).unwrap()
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, TokenError::NotInitialized))
+        .unwrap()
 }
 
 pub fn write_decimal(e: &Env, d: u8) {
@@ -13,7 +17,10 @@ pub fn write_decimal(e: &Env, d: u8) {
 
 pub fn read_name(e: &Env) -> Bytes {
     let key = DataKey::Name;
-    e.storage().get_unchecked(&key).unwrap()
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, TokenError::NotInitialized))
+        .unwrap()
 }
 
 pub fn write_name(e: &Env, d: Bytes) {
@@ -23,7 +30,10 @@ pub fn write_name(e: &Env, d: Bytes) {
 
 pub fn read_symbol(e: &Env) -> Bytes {
     let key = DataKey::Symbol;
-    e.storage().get_unchecked(&key).unwrap()
+    e.storage()
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(e, TokenError::NotInitialized))
+        .unwrap()
 }
 
 pub fn write_symbol(e: &Env, d: Bytes) {
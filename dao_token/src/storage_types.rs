@@ -18,4 +18,5 @@ pub enum DataKey {
     Decimals,
     Name,
     Symbol,
+    Supply,
 }
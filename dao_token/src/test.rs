@@ -1,7 +1,7 @@
 #![cfg(test)]
 extern crate std;
 
-use crate::{contract::Token, TokenClient};
+use crate::{contract::Token, dao::TokenError, TokenClient};
 use soroban_sdk::{symbol, testutils::Address as _, Address, Env, IntoVal};
 
 fn create_token(e: &Env, admin: &Address) -> TokenClient {
@@ -32,6 +32,7 @@ fn test() {
         )]
     );
     assert_eq!(token.balance(&user1), 1000);
+    assert_eq!(token.supply(), 1000);
 
     token.incr_allow(&user2, &user3, &500);
     assert_eq!(
@@ -85,6 +86,7 @@ fn test() {
             (&admin1, &admin2).into_val(&e),
         )]
     );
+    assert_eq!(token.admin(), admin2);
 
     token.set_auth(&admin2, &user2, &false);
     assert_eq!(
@@ -112,6 +114,7 @@ fn test() {
         )]
     );
     assert_eq!(token.balance(&user3), 200);
+    assert_eq!(token.supply(), 900);
 
     // Increase by 400, with an existing 100 = 500
     token.incr_allow(&user2, &user3, &400);
@@ -170,10 +173,10 @@ fn test_burn() {
     );
     assert_eq!(token.balance(&user1), 0);
     assert_eq!(token.balance(&user2), 0);
+    assert_eq!(token.supply(), 0);
 }
 
 #[test]
-#[should_panic(expected = "insufficient balance")]
 fn xfer_insufficient_balance() {
     let e: Env = Default::default();
     let admin = Address::random(&e);
@@ -184,11 +187,13 @@ fn xfer_insufficient_balance() {
     token.mint(&admin, &user1, &1000);
     assert_eq!(token.balance(&user1), 1000);
 
-    token.xfer(&user1, &user2, &1001);
+    assert_eq!(
+        token.try_xfer(&user1, &user2, &1001),
+        Err(Ok(TokenError::InsufficientBalance.into()))
+    );
 }
 
 #[test]
-#[should_panic(expected = "can't receive when deauthorized")]
 fn xfer_receive_deauthorized() {
     let e: Env = Default::default();
     let admin = Address::random(&e);
@@ -200,11 +205,13 @@ fn xfer_receive_deauthorized() {
     assert_eq!(token.balance(&user1), 1000);
 
     token.set_auth(&admin, &user2, &false);
-    token.xfer(&user1, &user2, &1);
+    assert_eq!(
+        token.try_xfer(&user1, &user2, &1),
+        Err(Ok(TokenError::ReceiveDeauthorized.into()))
+    );
 }
 
 #[test]
-#[should_panic(expected = "can't spend when deauthorized")]
 fn xfer_spend_deauthorized() {
     let e: Env = Default::default();
     let admin = Address::random(&e);
@@ -216,11 +223,13 @@ fn xfer_spend_deauthorized() {
     assert_eq!(token.balance(&user1), 1000);
 
     token.set_auth(&admin, &user1, &false);
-    token.xfer(&user1, &user2, &1);
+    assert_eq!(
+        token.try_xfer(&user1, &user2, &1),
+        Err(Ok(TokenError::SpendDeauthorized.into()))
+    );
 }
 
 #[test]
-#[should_panic(expected = "insufficient allowance")]
 fn xfer_from_insufficient_allowance() {
     let e: Env = Default::default();
     let admin = Address::random(&e);
@@ -235,29 +244,66 @@ fn xfer_from_insufficient_allowance() {
     token.incr_allow(&user1, &user3, &100);
     assert_eq!(token.allowance(&user1, &user3), 100);
 
-    token.xfer_from(&user3, &user1, &user2, &101);
+    assert_eq!(
+        token.try_xfer_from(&user3, &user1, &user2, &101),
+        Err(Ok(TokenError::InsufficientAllowance.into()))
+    );
 }
 
 #[test]
-#[should_panic(expected = "already initialized")]
 fn initialize_already_initialized() {
     let e: Env = Default::default();
     let admin = Address::random(&e);
     let token = create_token(&e, &admin);
 
-    token.initialize(&admin, &10, &"name".into_val(&e), &"symbol".into_val(&e));
+    assert_eq!(
+        token.try_initialize(&admin, &10, &"name".into_val(&e), &"symbol".into_val(&e)),
+        Err(Ok(TokenError::AlreadyInitialized.into()))
+    );
 }
 
 #[test]
-#[should_panic(expected = "Decimal must fit in a u8")]
 fn decimal_is_over_max() {
     let e = Default::default();
     let admin = Address::random(&e);
     let token = TokenClient::new(&e, &e.register_contract(None, Token {}));
-    token.initialize(
-        &admin,
-        &(u32::from(u8::MAX) + 1),
-        &"name".into_val(&e),
-        &"symbol".into_val(&e),
+    assert_eq!(
+        token.try_initialize(
+            &admin,
+            &(u32::from(u8::MAX) + 1),
+            &"name".into_val(&e),
+            &"symbol".into_val(&e),
+        ),
+        Err(Ok(TokenError::DecimalTooLarge.into()))
+    );
+}
+
+#[test]
+fn admin_and_amount_checks() {
+    let e: Env = Default::default();
+    let admin = Address::random(&e);
+    let user1 = Address::random(&e);
+    let user2 = Address::random(&e);
+
+    let token = TokenClient::new(&e, &e.register_contract(None, Token {}));
+    assert_eq!(
+        token.try_mint(&admin, &user1, &1000),
+        Err(Ok(TokenError::NotInitialized.into()))
+    );
+
+    token.initialize(&admin, &7, &"name".into_val(&e), &"symbol".into_val(&e));
+    assert_eq!(
+        token.try_mint(&user1, &user1, &1000),
+        Err(Ok(TokenError::NotAdmin.into()))
+    );
+    assert_eq!(
+        token.try_mint(&admin, &user1, &-1),
+        Err(Ok(TokenError::NegativeAmount.into()))
+    );
+
+    token.incr_allow(&user1, &user2, &i128::MAX);
+    assert_eq!(
+        token.try_incr_allow(&user1, &user2, &1),
+        Err(Ok(TokenError::AllowanceOverflow.into()))
     );
 }
@@ -99,7 +99,10 @@ async function init_dao(){
             // An operation to call increment on the contract
             dao_contract.call("init",
                 xdr.ScVal.scvObject(xdr.ScObject.scoBytes(Buffer.from("ba30bd6b7d519e0abadf6dc0fe100eda72fe6be5137a99fc8f44a8cd51d92b92","hex"))),
+                xdr.ScVal.scvObject(xdr.ScObject.scoVec([xdr.ScVal.scvSymbol("Timestamp")])),
                 xdr.ScVal.scvU32(3600),
+                xdr.ScVal.scvU32(604800),
+                xdr.ScVal.scvU32(604800),
                 xdr.ScVal.scvU32(10),
                 xdr.ScVal.scvObject(xdr.ScObject.scoI128(new xdr.Int128Parts({lo:xdr.Uint64.fromString("1"),hi:xdr.Uint64.fromString("0")}))),
             )